@@ -0,0 +1,143 @@
+use std::fmt;
+
+/// A range of file offsets mapped at a virtual address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub offset: usize,
+    pub len: usize,
+    pub address: usize,
+}
+
+impl Segment {
+    pub fn contains_offset(&self, offset: usize) -> bool {
+        offset >= self.offset && offset - self.offset < self.len
+    }
+
+    pub fn contains_address(&self, address: usize) -> bool {
+        address >= self.address && address - self.address < self.len
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}:{:#x}@{:#x}", self.offset, self.len, self.address)
+    }
+}
+
+/// Which addresses are displayed in the address column and used by goto
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSpace {
+    #[default]
+    FileOffset,
+    Virtual,
+}
+
+impl AddressSpace {
+    pub fn toggle(self) -> Self {
+        match self {
+            AddressSpace::FileOffset => AddressSpace::Virtual,
+            AddressSpace::Virtual => AddressSpace::FileOffset,
+        }
+    }
+}
+
+impl fmt::Display for AddressSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AddressSpace::FileOffset => "offset",
+            AddressSpace::Virtual => "VA",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Mapping of file offsets to virtual addresses: a base address where the
+/// whole file is loaded, or segments (e.g. sections of an executable)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AddressMap {
+    /// Sorted by offset
+    segments: Vec<Segment>,
+}
+
+impl AddressMap {
+    /// Whole file loaded at `base`
+    pub fn with_base(base: usize) -> Self {
+        AddressMap {
+            segments: vec![Segment {
+                offset: 0,
+                len: usize::MAX - base,
+                address: base,
+            }],
+        }
+    }
+
+    pub fn new(mut segments: Vec<Segment>) -> Self {
+        segments.sort_by_key(|segment| segment.offset);
+        AddressMap { segments }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Virtual address of the file offset, if mapped
+    pub fn address_of(&self, offset: usize) -> Option<usize> {
+        let idx = self
+            .segments
+            .partition_point(|segment| segment.offset <= offset);
+        // the segment starting right before `offset`, or an earlier bigger one
+        self.segments[..idx]
+            .iter()
+            .rev()
+            .find(|segment| segment.contains_offset(offset))
+            .map(|segment| segment.address + offset - segment.offset)
+    }
+
+    /// File offset of the virtual address, if mapped
+    pub fn offset_of(&self, address: usize) -> Option<usize> {
+        self.segments
+            .iter()
+            .find(|segment| segment.contains_address(address))
+            .map(|segment| segment.offset + address - segment.address)
+    }
+
+    /// Highest virtual address of the first `size` bytes of the file
+    pub fn last_address(&self, size: usize) -> usize {
+        self.segments
+            .iter()
+            .filter(|segment| segment.offset < size)
+            .map(|segment| {
+                let len = std::cmp::min(segment.len, size - segment.offset);
+                segment.address + len
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Parses an address, in hexadecimal with a `0x` prefix, else in decimal
+pub fn parse_address(s: &str) -> Result<usize, String> {
+    let s = s.trim();
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(&hex.replace('_', ""), 16),
+        None => s.replace('_', "").parse(),
+    };
+    parsed.map_err(|_| format!("'{s}' is not a valid address"))
+}
+
+/// Parses a segment given as `OFFSET:LEN@ADDRESS`
+pub fn parse_segment(s: &str) -> Result<Segment, String> {
+    let err = || format!("'{s}' is not a valid segment, expected OFFSET:LEN@ADDRESS");
+    let (range, address) = s.split_once('@').ok_or_else(err)?;
+    let (offset, len) = range.split_once(':').ok_or_else(err)?;
+
+    Ok(Segment {
+        offset: parse_address(offset)?,
+        len: parse_address(len)?,
+        address: parse_address(address)?,
+    })
+}
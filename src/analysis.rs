@@ -0,0 +1,97 @@
+/// Coarse classification of a block of bytes, used to colour the minimap
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass {
+    #[default]
+    Zero,
+    Ff,
+    Ascii,
+    Binary,
+}
+
+pub fn byte_histogram(bytes: &[u8]) -> [usize; 256] {
+    let mut histogram = [0; 256];
+    for b in bytes {
+        histogram[*b as usize] += 1;
+    }
+
+    histogram
+}
+
+/// Shannon entropy in bits per byte, from 0.0 (constant data) to 8.0
+/// (uniformly distributed data)
+pub fn entropy_from_histogram(histogram: &[usize; 256], total: usize) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    let total = total as f64;
+    histogram
+        .iter()
+        .filter(|&&count| count != 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+pub fn shannon_entropy(bytes: &[u8]) -> f64 {
+    entropy_from_histogram(&byte_histogram(bytes), bytes.len())
+}
+
+/// Returns the dominant class of the block. Padding (0x00 or 0xFF) is only
+/// reported when the whole block consists of it.
+pub fn classify(bytes: &[u8]) -> ByteClass {
+    if bytes.iter().all(|&b| b == 0) {
+        ByteClass::Zero
+    } else if bytes.iter().all(|&b| b == 0xff) {
+        ByteClass::Ff
+    } else if is_mostly_printable(bytes) {
+        ByteClass::Ascii
+    } else {
+        ByteClass::Binary
+    }
+}
+
+pub fn is_printable(b: u8) -> bool {
+    b.is_ascii_graphic() || b == b' ' || b == b'\t' || b == b'\n' || b == b'\r'
+}
+
+fn is_mostly_printable(bytes: &[u8]) -> bool {
+    let printable = bytes.iter().filter(|&&b| is_printable(b)).count();
+    // at least 90% of printable characters
+    printable * 10 >= bytes.len() * 9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entropy() {
+        assert_eq!(shannon_entropy(&[]), 0.0);
+        assert_eq!(shannon_entropy(&[0x42; 100]), 0.0);
+        assert_eq!(shannon_entropy(b"abab"), 1.0);
+        assert_eq!(shannon_entropy(b"abcd"), 2.0);
+        let all = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(shannon_entropy(&all), 8.0);
+        let histogram = byte_histogram(&all.repeat(3));
+        assert!(histogram.iter().all(|&count| count == 3));
+        assert_eq!(entropy_from_histogram(&histogram, 3 * 256), 8.0);
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(classify(&[0; 16]), ByteClass::Zero);
+        assert_eq!(classify(&[0xff; 16]), ByteClass::Ff);
+        assert_eq!(classify(b"Hello, world!\r\n\tok"), ByteClass::Ascii);
+        // padding only when the whole block is
+        assert_eq!(classify(&[0, 0, 0, 0xff]), ByteClass::Binary);
+        // 90% of printable characters at least
+        let mut text = b"a".repeat(9);
+        text.push(0x80);
+        assert_eq!(classify(&text), ByteClass::Ascii);
+        text.push(0x80);
+        assert_eq!(classify(&text), ByteClass::Binary);
+    }
+}
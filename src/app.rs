@@ -1,17 +1,30 @@
 use log::debug;
 use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::Duration;
 
 use crate::{
-    display,
+    addressing::{AddressMap, AddressSpace},
+    archive::Method,
+    buffer::Buffer,
+    diff::Side,
+    display::{self, DisplayOptions},
+    encoding::{CharTable, Encoding},
+    export::ExportOptions,
     file::FileInfo,
+    hash::{self, HashAlgorithm},
+    minimap::MINIMAP_WIDTH,
     popup::{Popup, centered_rect_length, centered_rect_percent},
+    stats::Statistics,
+    strings::StringsView,
+    worker::{WorkerMsg, WorkerSender},
 };
 use ratatui::{
     Frame,
     layout::{Constraint, Flex, Layout},
-    text::Text,
-    widgets::{Block, Borders, Paragraph},
+    style::{Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs},
 };
 use ratatui::{
     Terminal,
@@ -22,50 +35,146 @@ use ratatui::{
 
 const ADDR_PANE_PADDING: u16 = 4;
 const MIN_BYTES_PER_ROW: u16 = 8;
+/// Delay between two redraws when no event is received, so that background
+/// results get displayed
+const TICK_RATE: Duration = Duration::from_millis(100);
 
 const WIDTH_ERROR_TEXT: &str = "Terminal is not wide enough..";
 const HELP_FOOTER: &str = "Press (h) for help";
 const DETAILS_FOOTER: &str = "Press (i) for file details";
 
+const FILEINFO_HELP: &str = "Enter: compute | a: compute all | y: copy | Esc: close";
+
+const MAPPINGS_HELP: &str = "Enter: jump | Esc: close";
+const MEMBERS_HELP: &str = "Enter: jump | o: open | Esc: close";
+const BOOKMARKS_HELP: &str = "Enter: jump | d: delete | Esc: close";
+const BUFFERS_HELP: &str = "Enter: open | Esc: close";
+
 const HELP_BODY: &str = r#"
 h:        Toggle this help dialog
 q:        Quit the application
-j:        Move one line down
-k:        Move one line up
+j:        Move cursor one line down
+k:        Move cursor one line up
+Left:     Move cursor one byte left
+Right:    Move cursor one byte right
 PageUp:   Move one page up
 PageDown: Move one page down
 ctrl+u:   Move half page up
 ctrl+d:   Move half page down
-g:        Go to start
+gg:       Go to start
 G:        Go to end
-i:        Get file details
+i:        Get file details (hashes of the selection or whole file)
+v:        Start/stop selection
+s:        Get statistics (selection or whole file)
+S:        Open strings panel
+]c:       Go to next difference (diff mode)
+[c:       Go to previous difference (diff mode)
+D:        Get diff summary (diff mode)
+o:        Toggle minimap (overview of the file)
+O:        Switch minimap mode (entropy/byte class)
+]m:       Go to next mapped range (process, firmware, virtual addresses)
+[m:       Go to previous mapped range
+P:        List memory mappings (process)
+L:        List members of an archive (zip, tar, cpio, ar)
+E:        Switch encoding of the character pane
+r:        Switch data radix (hex, decimal, octal, binary)
+w:        Switch word size (1, 2, 4, 8 bytes)
+e:        Switch byte order of words (little, big endian)
+f:        Switch word format (raw, unsigned, signed, float)
+R:        Switch address radix (hex, decimal)
+U:        Switch case of hexadecimal digits
+A:        Switch addresses between file offsets and virtual addresses
+F:        Toggle follow mode (stay at the end when the file grows)
+gt:       Go to next buffer
+gT:       Go to previous buffer
+B:        List opened buffers
+m{a-z}:   Set mark at the cursor
+'{a-z}:   Go to mark
+b:        List bookmarks and marks
+Z:        Open the compressed stream at the cursor (or the file) decompressed
+X:        Export the selection or whole file (prompt)
+SPACE:    Open command prompt (goto <address>, open <file>, bnext, bprev,
+          buffers, bookmark <name> [comment], bookmarks,
+          region <label> [color=COLOR] [note], region, unregion [label],
+          base <address>, map [OFFSET:LEN@ADDRESS], unmap,
+          save [raw|ihex|srec|ti-txt] <file>, decompress [format],
+          export <format> [name=ID] [width=N] [file], quit)
 "#;
 
 pub struct App {
-    pub scroll_pos: usize,
     pub vertical_margin: usize,
     pub frame_size: (u16, u16),
     pub show_help: bool,
     pub show_fileinfo: bool,
-    pub fileinfo: FileInfo,
+    pub show_stats: bool,
     pub quit: bool, // exit state
     pub bytes_per_row: usize,
     pub blocksize: u16, // inherited from cli flags
+    pub display: DisplayOptions,
+    /// Custom decoding of the character pane, inherited from cli flags
+    pub char_table: Option<CharTable>,
+    /// Layout of the `export` command, inherited from cli flags
+    pub export: ExportOptions,
+    /// Whether `:save` may overwrite the opened file, inherited from cli flags
+    pub allow_write: bool,
+    /// Displayed buffer, its slot in `buffers` holds a placeholder
+    pub buffer: Buffer,
+    pub buffers: Vec<Buffer>,
+    /// Index of the displayed buffer in `buffers`
+    pub current: usize,
+    pub show_buffers: bool,
+    /// Memory mappings popup, for processes
+    pub show_mappings: bool,
+    pub mappings_selected: usize,
+    /// Archive member list popup
+    pub show_members: bool,
+    pub members_selected: usize,
+    /// Bookmark list popup
+    pub show_bookmarks: bool,
+    pub bookmarks_selected: usize,
+    /// Buffer highlighted in the buffer list popup
+    pub buffers_selected: usize,
+    /// Short feedback displayed at the bottom of popups
+    pub message: Option<String>,
+    pub show_diff: bool,
+    /// First key of a two keys command (e.g. `]c`)
+    pub pending_key: Option<char>,
+    /// Text typed in the command prompt, `None` when it is closed
+    pub command: Option<String>,
+    pub worker_tx: Sender<(usize, u64, WorkerMsg)>,
+    pub worker_rx: Receiver<(usize, u64, WorkerMsg)>,
 }
 
 impl App {
     pub fn new(
-        filename: String,
+        filenames: Vec<String>,
+        diff_with: Option<String>,
+        align: bool,
+        allow_write: bool,
+        follow: bool,
         blocksize: Option<u16>,
         frame_size: (u16, u16),
     ) -> io::Result<Self> {
-        let fileinfo = FileInfo::new(&filename)?;
-        debug!("'{filename}' information retrieved");
         let mut app = App {
-            fileinfo,
             frame_size,
+            allow_write,
             ..App::default()
         };
+        for (i, filename) in filenames.iter().enumerate() {
+            let diff_with = if i == 0 { diff_with.as_deref() } else { None };
+            let tx = WorkerSender::new(i, app.worker_tx.clone());
+            app.buffers
+                .push(Buffer::open(filename, diff_with, align, allow_write, tx)?);
+        }
+        for buffer in app.buffers.iter_mut() {
+            buffer.follow = follow;
+        }
+        app.buffer = std::mem::take(&mut app.buffers[0]);
+        app.message = app.buffer.regions.error.clone();
+        if follow {
+            app.buffer.cursor = app.buffer.fileinfo.size.saturating_sub(1);
+            app.scroll_to_cursor();
+        }
 
         // if specified in CLI, change default value
         if let Some(blocksize) = blocksize {
@@ -86,28 +195,222 @@ impl App {
                 break;
             }
 
-            if event::poll(Duration::from_millis(2000))? {
+            if event::poll(TICK_RATE)? {
                 let event = event::read()?;
                 self.handle_event(event);
             }
+            self.handle_worker_msgs();
+            self.refresh_stats();
             terminal.draw(|f| self.draw(f))?;
         }
 
         Ok(())
     }
 
+    fn handle_worker_msgs(&mut self) {
+        while let Ok((idx, generation, msg)) = self.worker_rx.try_recv() {
+            let reloaded = matches!(
+                msg,
+                WorkerMsg::FileChanged { .. } | WorkerMsg::FileLoaded(_)
+            );
+            let applied = self
+                .buffer_mut(idx)
+                .is_some_and(|buffer| buffer.handle_worker_msg(generation, msg));
+            if applied && reloaded && idx == self.current {
+                self.scroll_to_cursor();
+            }
+        }
+    }
+
+    fn buffer_mut(&mut self, idx: usize) -> Option<&mut Buffer> {
+        if idx == self.current {
+            Some(&mut self.buffer)
+        } else {
+            self.buffers.get_mut(idx)
+        }
+    }
+
+    /// Sender for the background threads working on the displayed buffer
+    fn worker_sender(&self) -> WorkerSender {
+        self.buffer.tx.clone()
+    }
+
+    /// Opens `filename` in a new buffer and displays it
+    pub fn open(&mut self, filename: &str) -> io::Result<()> {
+        let idx = self.buffers.len();
+        let tx = WorkerSender::new(idx, self.worker_tx.clone());
+        let buffer = Buffer::open(filename, None, false, self.allow_write, tx)?;
+        self.buffers.push(buffer);
+        self.switch_buffer(idx);
+        Ok(())
+    }
+
+    /// Displays `fileinfo`, built in memory, in a new buffer
+    pub fn open_fileinfo(&mut self, fileinfo: FileInfo) {
+        let idx = self.buffers.len();
+        let tx = WorkerSender::new(idx, self.worker_tx.clone());
+        self.buffers.push(Buffer::new(fileinfo, None, tx));
+        self.switch_buffer(idx);
+    }
+
+    /// Displays the buffer at `idx` in `buffers`
+    pub fn switch_buffer(&mut self, idx: usize) {
+        if idx == self.current || idx >= self.buffers.len() {
+            return;
+        }
+        std::mem::swap(&mut self.buffer, &mut self.buffers[self.current]);
+        std::mem::swap(&mut self.buffer, &mut self.buffers[idx]);
+        self.current = idx;
+        // the cursor may have moved while in the background (follow mode)
+        self.scroll_to_cursor();
+        if let Some(err) = &self.buffer.regions.error {
+            self.message = Some(err.clone());
+        }
+        debug!("switch to buffer {idx} ('{}')", self.buffer.fileinfo.name);
+    }
+
+    pub fn next_buffer(&mut self) {
+        self.switch_buffer((self.current + 1) % self.buffers.len());
+    }
+
+    pub fn previous_buffer(&mut self) {
+        let len = self.buffers.len();
+        self.switch_buffer((self.current + len - 1) % len);
+    }
+
+    /// Name of the buffer at `idx` in `buffers`
+    fn buffer_name(&self, idx: usize) -> &str {
+        if idx == self.current {
+            &self.buffer.fileinfo.name
+        } else {
+            &self.buffers[idx].fileinfo.name
+        }
+    }
+
+    /// Whether a popup is drawn over the panes
+    fn any_popup(&self) -> bool {
+        self.show_help
+            || self.show_fileinfo
+            || self.show_stats
+            || self.show_diff
+            || self.show_buffers
+            || self.show_mappings
+            || self.show_members
+            || self.show_bookmarks
+            || self.buffer.strings.visible
+    }
+
+    /// Uses `address_map` for the virtual addresses of all buffers
+    pub fn set_address_map(&mut self, address_map: AddressMap) {
+        debug!("address map: {address_map:?}");
+        for buffer in self.buffers.iter_mut() {
+            buffer.set_address_map(address_map.clone());
+        }
+        self.buffer.set_address_map(address_map);
+        self.scroll_to_cursor();
+    }
+
+    /// Moves the cursor to `address`, in the displayed address space
+    pub fn goto(&mut self, address: usize) -> Result<(), String> {
+        // gaps between the virtual addresses are shown too
+        if let Some(span) = self.buffer.span() {
+            if !span.contains(&address) {
+                return Err(format!(
+                    "{address:#x} is out of the virtual addresses ({:#x}..{:#x})",
+                    span.start, span.end
+                ));
+            }
+            self.move_cursor_to(address - span.start);
+            return Ok(());
+        }
+        match self.buffer.offset_of(address) {
+            Some(offset) if offset < self.buffer.fileinfo.size => {
+                self.move_cursor_to_offset(offset);
+                Ok(())
+            }
+            _ => Err(format!(
+                "{address:#x} is out of the file ({})",
+                self.buffer.address_space
+            )),
+        }
+    }
+
+    /// Height of the header: tabs, only displayed with several buffers, and
+    /// the warning banner of the displayed file
+    pub fn header_height(&self) -> u16 {
+        let tabs = if self.buffers.len() > 1 { 1 } else { 0 };
+        let banner = if self.buffer.fileinfo.banner().is_some() {
+            1
+        } else {
+            0
+        };
+        tabs + banner
+    }
+
+    /// Computes the statistics of the selection, or of the whole file if
+    /// nothing is selected, in the background while their popup is open
+    pub fn refresh_stats(&mut self) {
+        if self.show_stats {
+            let range = self.selection_or_file();
+            let buffer = &mut self.buffer;
+            buffer
+                .stats
+                .request(buffer.fileinfo.content.clone(), range, buffer.tx.clone());
+        }
+    }
+
+    /// Hashes the selection, or the whole file if nothing is selected, in the
+    /// background
+    pub fn compute_hashes(&mut self, algorithms: Vec<HashAlgorithm>) {
+        let range = self.selection_or_file();
+        let job = self.buffer.hashes.start_job(range.clone(), &algorithms);
+        debug!("hash job {job}: {algorithms:?} over {range:?}");
+        hash::spawn_worker(
+            job,
+            self.buffer.fileinfo.content.clone(),
+            range,
+            algorithms,
+            self.worker_sender(),
+        );
+    }
+
+    /// Extracts strings again, after a change of settings
+    pub fn refresh_strings(&mut self) {
+        let tx = self.worker_sender();
+        self.buffer
+            .strings
+            .spawn_worker(self.buffer.fileinfo.content.clone(), tx);
+    }
+
+    fn minimap_width(&self) -> u16 {
+        if self.buffer.minimap.visible {
+            MINIMAP_WIDTH
+        } else {
+            0
+        }
+    }
+
     pub fn draw(&mut self, frame: &mut Frame) {
         let area = frame.area();
-        let filesize = self.fileinfo.size;
-        let nb_digits_addr = display::count_hexdigits(filesize) as u16;
+        let filesize = self.data_size();
+        let nb_digits_addr =
+            display::count_digits(self.last_address(), self.display.address_radix) as u16;
+        let cell_width = self.display.cell_width() as u16;
+        let group = self.display.group as u16;
+        let block_words = std::cmp::max(self.blocksize / group, 1);
+        // a row holds at least one whole block
+        let min_words = std::cmp::max(MIN_BYTES_PER_ROW / group, block_words);
+        // two hex and ascii panes in diff mode
+        let panes: u16 = if self.buffer.diff.is_some() { 2 } else { 1 };
 
         // --- Compute min width
-        let min_width_body = (MIN_BYTES_PER_ROW /* min bytes per line */ * 3 + /* width per byte in hex view */
-            MIN_BYTES_PER_ROW/self.blocksize-1 + /* additional space every `blocksize` bytes in hex view */
-            MIN_BYTES_PER_ROW +                  /* nb ascii bytes */
-            3) +                                 /* ratatui padding bytes (borders,...) */
+        let min_width_body = (min_words /* min words per line */ * (cell_width + 1) + /* width per word in hex view */
+            (min_words/block_words).saturating_sub(1) + /* additional space every `blocksize` bytes in hex view */
+            min_words * group +                  /* nb ascii bytes */
+            3) * panes +                         /* ratatui padding bytes (borders,...) */
             ADDR_PANE_PADDING +                  /* address width padding */
-            nb_digits_addr                       /* address width */;
+            nb_digits_addr +                     /* address width */
+            self.minimap_width();
         let min_width_footer = HELP_FOOTER.len() + DETAILS_FOOTER.len() + 6 /* padding */;
         let min_width = std::cmp::max(min_width_body, min_width_footer as u16);
 
@@ -121,36 +424,77 @@ impl App {
             return;
         }
 
-        // update bytes_per_row according to terminal width
+        // update bytes_per_row according to terminal width, shared between panes
+        let addr_width = ADDR_PANE_PADDING + nb_digits_addr;
+        let pane_width =
+            (self.frame_size.0 - self.minimap_width() - addr_width - (panes - 1)) / panes;
         self.bytes_per_row = display::get_bytes_per_row(
-            self.frame_size.0,
-            ADDR_PANE_PADDING + nb_digits_addr,
+            pane_width + addr_width,
+            addr_width,
             self.blocksize,
+            cell_width,
+            group,
         );
 
         // --- layout
-        let screen =
-            Layout::vertical([Constraint::Fill(0), Constraint::Length(1) /* Footer */]).split(area);
-        let body = Layout::horizontal([
-            Constraint::Length(nb_digits_addr + ADDR_PANE_PADDING),
-            Constraint::Fill(1),
-            Constraint::Length(self.bytes_per_row as u16 + 1 /* padding */),
-            Constraint::Length(1), // Scrollbar
+        let screen = Layout::vertical([
+            Constraint::Length(self.header_height()), /* Tabs and banner */
+            Constraint::Fill(0),
+            Constraint::Length(1), /* Footer */
         ])
-        .split(screen[0]);
+        .split(area);
+        let mut constraints = vec![Constraint::Length(addr_width)];
+        for _ in 0..panes {
+            constraints.push(Constraint::Fill(1));
+            constraints.push(Constraint::Length(
+                self.bytes_per_row as u16 + 1, /* padding */
+            ));
+        }
+        constraints.push(Constraint::Length(self.minimap_width()));
+        constraints.push(Constraint::Length(1)); // Scrollbar
+        let body = Layout::horizontal(constraints).split(screen[1]);
 
         // --- range of displayed data
-        let start_line_idx = self.scroll_pos;
+        let start_line_idx = self.buffer.scroll_pos;
         // sub margin because compared to frame size, only height-margin lines are rendered
-        let mut end_line_idx = self.scroll_pos + self.frame_size.1 as usize - self.vertical_margin;
+        let mut end_line_idx = self.buffer.scroll_pos + self.page_height();
         if end_line_idx * self.bytes_per_row > filesize {
             end_line_idx = filesize.div_ceil(self.bytes_per_row);
         }
 
+        // --- Header
+        let header =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).split(screen[0]);
+        let mut header_lines = header.iter();
+        if self.buffers.len() > 1
+            && let Some(&line) = header_lines.next()
+        {
+            let names = (0..self.buffers.len())
+                .map(|idx| format!("{idx}:{}", self.buffer_name(idx)))
+                .collect::<Vec<_>>();
+            let tabs = Tabs::new(names)
+                .select(self.current)
+                .highlight_style(Style::default().reversed());
+            frame.render_widget(tabs, line);
+        }
+        if let Some(banner) = self.buffer.fileinfo.banner()
+            && let Some(&line) = header_lines.next()
+        {
+            let style = if self.buffer.fileinfo.writable {
+                Style::default().black().on_red().bold()
+            } else {
+                Style::default().black().on_yellow()
+            };
+            frame.render_widget(Line::from(banner).style(style), line);
+        }
+
         // --- Address view
         let address = self.get_address_to_lines(start_line_idx, end_line_idx);
         let address_block = Block::default()
-            .title("Address")
+            .title(match self.buffer.address_space {
+                AddressSpace::FileOffset => "Address",
+                AddressSpace::Virtual => "VA",
+            })
             .borders(Borders::LEFT | Borders::TOP | Borders::BOTTOM);
 
         let address_view = Paragraph::new(address)
@@ -159,20 +503,42 @@ impl App {
 
         frame.render_widget(address_view, body[0]);
 
-        // --- Hex view
-        let hexdump = self.get_hexdump(start_line_idx, end_line_idx);
-        let hex_block = Block::default().title("Hex").borders(Borders::ALL);
-        let hex_view = Paragraph::new(hexdump).block(hex_block);
+        // --- Hex and Ascii views, for each file
+        let mut files = vec![(Side::Left, &self.buffer.fileinfo)];
+        if let Some(diff) = &self.buffer.diff {
+            files.push((Side::Right, &diff.other));
+        }
+        for (i, (side, fileinfo)) in files.into_iter().enumerate() {
+            let hex_title = if panes > 1 {
+                format!("{} - {}", self.display.title(), fileinfo.name)
+            } else {
+                self.display.title()
+            };
+            let ascii_title = match (&self.char_table, self.display.encoding) {
+                (Some(table), Encoding::Custom) => format!("Table - {}", table.name),
+                (_, encoding) => String::from(encoding.title()),
+            };
+
+            let hexdump = self.get_hexdump(side, start_line_idx, end_line_idx);
+            let hex_block = Block::default().title(hex_title).borders(Borders::ALL);
+            let hex_view = Paragraph::new(hexdump).block(hex_block);
+            frame.render_widget(hex_view, body[1 + 2 * i]);
 
-        frame.render_widget(hex_view, body[1]);
+            let asciidump = self.get_asciidump(side, start_line_idx, end_line_idx);
+            let ascii_block = Block::default()
+                .title(ascii_title)
+                .borders(Borders::RIGHT | Borders::TOP | Borders::BOTTOM);
+            let ascii_view = Paragraph::new(asciidump).block(ascii_block);
+            frame.render_widget(ascii_view, body[2 + 2 * i]);
+        }
 
-        // --- Ascii view
-        let asciidump = self.get_asciidump(start_line_idx, end_line_idx);
-        let ascii_block = Block::default()
-            .title("Ascii")
-            .borders(Borders::RIGHT | Borders::TOP | Borders::BOTTOM);
-        let ascii_view = Paragraph::new(asciidump).block(ascii_block);
-        frame.render_widget(ascii_view, body[2]);
+        // --- Minimap
+        let minimap_area = body[body.len() - 2];
+        self.buffer.minimap.area = minimap_area;
+        if self.buffer.minimap.visible {
+            self.buffer.minimap.rows = self.minimap_rows(minimap_area.height);
+            frame.render_widget(&self.buffer.minimap, minimap_area);
+        }
 
         // --- Scrollbar
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight).track_symbol(None);
@@ -180,36 +546,278 @@ impl App {
         let mut scrollbar_state = ScrollbarState::default()
             .content_length(content_len)
             .viewport_content_length(4)
-            .position(self.scroll_pos);
-        frame.render_stateful_widget(scrollbar, body[3], &mut scrollbar_state);
+            .position(self.buffer.scroll_pos);
+        frame.render_stateful_widget(scrollbar, body[body.len() - 1], &mut scrollbar_state);
 
         // --- Footer
-        let footer_chunks = Layout::horizontal([
-            Constraint::Length(HELP_FOOTER.len() as u16),
-            Constraint::Length(DETAILS_FOOTER.len() as u16),
-        ])
-        .horizontal_margin(2)
-        .flex(Flex::SpaceBetween)
-        .split(screen[1]);
+        let radix = self.display.address_radix;
+        let mut position = match self.selection() {
+            Some(selection) => format!(
+                "{}..{} ({} bytes)",
+                radix.format_prefixed(self.address_of(selection.start)),
+                radix.format_prefixed(self.address_of(selection.end - 1) + 1),
+                selection.len()
+            ),
+            None => radix.format_prefixed(self.address_of(self.buffer.cursor)),
+        };
+        if self.buffer.follow {
+            position.push_str(" [follow]");
+        }
+        // mapping of a process under the cursor
+        let fileinfo = &self.buffer.fileinfo;
+        match self.buffer.offset_at(self.buffer.cursor) {
+            Some(offset) => {
+                if let Some(idx) = fileinfo.mapping_at(offset) {
+                    let mapping = &fileinfo.mappings[idx];
+                    position.push_str(&format!(" {} {}", mapping.perms, mapping.path));
+                }
+            }
+            None => position.push_str(" [unmapped]"),
+        }
+        if let Some(region) = self.region_at_cursor() {
+            position.push_str(&format!(" <{}>", region.label));
+        }
+        if let Some(command) = &self.command {
+            // the command prompt replaces the footer while typing
+            frame.render_widget(Line::from(format!(":{command}")), screen[2]);
+        } else if let Some(message) = self.message.as_deref().filter(|_| !self.any_popup()) {
+            frame.render_widget(Line::from(message), screen[2]);
+        } else {
+            let footer_chunks = Layout::horizontal([
+                Constraint::Length(HELP_FOOTER.len() as u16),
+                Constraint::Length(position.len() as u16),
+                Constraint::Length(DETAILS_FOOTER.len() as u16),
+            ])
+            .horizontal_margin(2)
+            .flex(Flex::SpaceBetween)
+            .split(screen[2]);
 
-        let left_footer = Text::from(HELP_FOOTER);
-        let right_footer = Text::from(DETAILS_FOOTER);
-        frame.render_widget(left_footer, footer_chunks[0]);
-        frame.render_widget(right_footer, footer_chunks[1]);
+            let left_footer = Text::from(HELP_FOOTER);
+            let middle_footer = Text::from(position);
+            let right_footer = Text::from(DETAILS_FOOTER);
+            frame.render_widget(left_footer, footer_chunks[0]);
+            frame.render_widget(middle_footer, footer_chunks[1]);
+            frame.render_widget(right_footer, footer_chunks[2]);
+        }
 
         // --- Help popup
         if self.show_help {
-            let popup_rect = centered_rect_percent(area, 30, 40);
+            let height = HELP_BODY.lines().count() as u16 + 2 /* borders */;
+            let popup_rect = centered_rect_length(area, 60, height);
             let popup = Popup::default().title("Help").content(HELP_BODY);
             frame.render_widget(popup, popup_rect);
         }
 
         // --- Fileinfo popup
         if self.show_fileinfo {
-            let popup_rect = centered_rect_percent(area, 50, 25);
+            let popup_rect = centered_rect_percent(area, 70, 70);
+            let mut content = self.buffer.fileinfo.to_text();
+            content.push_line("");
+            content.extend(self.buffer.hashes.to_lines());
+            let footer = self.message.as_deref().unwrap_or(FILEINFO_HELP);
             let popup = Popup::default()
                 .title("File details")
-                .content(self.fileinfo.to_text());
+                .content(content)
+                .footer(footer);
+            frame.render_widget(popup, popup_rect);
+        }
+
+        // --- Statistics popup
+        if self.show_stats {
+            let popup_rect = centered_rect_percent(area, 80, 70);
+            // the histogram is as wide as the popup, less its borders
+            let bucket_size = Statistics::bucket_size(popup_rect.width.saturating_sub(2));
+            let title = if bucket_size == 1 {
+                String::from("Statistics (histogram of the 256 byte values)")
+            } else {
+                format!(
+                    "Statistics (histogram of {} bars of {bucket_size} byte values each)",
+                    256 / bucket_size
+                )
+            };
+            let block = Block::default().title(title).borders(Borders::ALL);
+            let inner = block.inner(popup_rect);
+            frame.render_widget(Clear, popup_rect);
+            frame.render_widget(block, popup_rect);
+
+            match self.buffer.stats.get() {
+                Some(stats) => {
+                    let chunks =
+                        Layout::vertical([Constraint::Length(8), Constraint::Fill(1)]).split(inner);
+                    frame.render_widget(Paragraph::new(stats.to_text()), chunks[0]);
+
+                    let chart_block = Block::default()
+                        .title(format!("Histogram 0x00..0xff ({bucket_size} per bar)"))
+                        .borders(Borders::TOP);
+                    let chart = stats.histogram_chart(chunks[1].width).block(chart_block);
+                    frame.render_widget(chart, chunks[1]);
+                }
+                None => frame.render_widget(Paragraph::new("computing.."), inner),
+            }
+        }
+
+        // --- Diff summary popup
+        if self.show_diff
+            && let Some(diff) = &self.buffer.diff
+        {
+            let popup_rect = centered_rect_percent(area, 50, 60);
+            let popup = Popup::default()
+                .title(format!("Diff with {}", diff.other.name))
+                .content(diff.to_text());
+            frame.render_widget(popup, popup_rect);
+        }
+
+        // --- Strings panel
+        if self.buffer.strings.visible {
+            let popup_rect = centered_rect_percent(area, 80, 80);
+            let address_of = |offset| self.buffer.address_of(offset).unwrap_or(offset);
+            let view = StringsView {
+                panel: &self.buffer.strings,
+                address_of: &address_of,
+            };
+            frame.render_widget(view, popup_rect);
+        }
+
+        // --- Memory mappings popup
+        if self.show_mappings {
+            let popup_rect = centered_rect_percent(area, 80, 70);
+            frame.render_widget(Clear, popup_rect);
+            let items = self
+                .buffer
+                .fileinfo
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    let mut text = format!(
+                        "{:012x}-{:012x} {} {}",
+                        mapping.start, mapping.end, mapping.perms, mapping.path
+                    );
+                    if mapping.len == 0 {
+                        text.push_str(" (not read)");
+                    } else if !mapping.unreadable.is_empty() {
+                        let count: usize = mapping.unreadable.iter().map(|r| r.len()).sum();
+                        text.push_str(&format!(" ({count} bytes unreadable)"));
+                    }
+                    let item = ListItem::new(text);
+                    if mapping.len == 0 {
+                        item.dark_gray()
+                    } else {
+                        item
+                    }
+                })
+                .collect::<Vec<_>>();
+            let block = Block::default()
+                .title(format!("Memory mappings of {}", self.buffer.fileinfo.name))
+                .title_bottom(Line::from(MAPPINGS_HELP).dark_gray())
+                .borders(Borders::ALL);
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(Style::default().reversed());
+            let mut state = ListState::default().with_selected(Some(self.mappings_selected));
+            frame.render_stateful_widget(list, popup_rect, &mut state);
+        }
+
+        // --- Archive member list popup
+        if self.show_members
+            && let Some(archive) = &self.buffer.archive
+        {
+            let popup_rect = centered_rect_percent(area, 80, 70);
+            frame.render_widget(Clear, popup_rect);
+            let items = archive
+                .members
+                .iter()
+                .map(|member| {
+                    let method = match member.method {
+                        Method::Stored => member.method.to_string(),
+                        method => format!("{method} {}", member.size),
+                    };
+                    let text = format!(
+                        "{:#010x} {:>12} {:<16} {}",
+                        member.header, member.len, method, member.name
+                    );
+                    let item = ListItem::new(text);
+                    if member.is_dir {
+                        item.dark_gray()
+                    } else {
+                        item
+                    }
+                })
+                .collect::<Vec<_>>();
+            let block = Block::default()
+                .title(format!(
+                    "Members of {} ({}, {} members)",
+                    self.buffer.fileinfo.name,
+                    archive.format.name(),
+                    archive.members.len()
+                ))
+                .title_bottom(
+                    Line::from(self.message.as_deref().unwrap_or(MEMBERS_HELP)).dark_gray(),
+                )
+                .borders(Borders::ALL);
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(Style::default().reversed());
+            let mut state = ListState::default().with_selected(Some(self.members_selected));
+            frame.render_stateful_widget(list, popup_rect, &mut state);
+        }
+
+        // --- Bookmark list popup
+        if self.show_bookmarks {
+            let popup_rect = centered_rect_percent(area, 80, 70);
+            frame.render_widget(Clear, popup_rect);
+            let radix = self.display.address_radix;
+            let items = self
+                .buffer
+                .bookmarks
+                .list
+                .iter()
+                .map(|bookmark| {
+                    let address = self
+                        .buffer
+                        .address_of(bookmark.offset)
+                        .unwrap_or(bookmark.offset);
+                    ListItem::new(format!(
+                        "{:<16} {:>14}  {}",
+                        bookmark.name,
+                        radix.format_prefixed(address),
+                        bookmark.comment
+                    ))
+                })
+                .collect::<Vec<_>>();
+            let block = Block::default()
+                .title(format!("Bookmarks of {}", self.buffer.fileinfo.name))
+                .title_bottom(
+                    Line::from(self.message.as_deref().unwrap_or(BOOKMARKS_HELP)).dark_gray(),
+                )
+                .borders(Borders::ALL);
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(Style::default().reversed());
+            let mut state = ListState::default().with_selected(Some(self.bookmarks_selected));
+            frame.render_stateful_widget(list, popup_rect, &mut state);
+        }
+
+        // --- Buffer list popup
+        if self.show_buffers {
+            let lines = (0..self.buffers.len())
+                .map(|idx| {
+                    let mut line = Line::from(format!("{idx}: {}", self.buffer_name(idx)));
+                    if idx == self.current {
+                        line = line.bold();
+                    }
+                    if idx == self.buffers_selected {
+                        line.reversed()
+                    } else {
+                        line
+                    }
+                })
+                .collect::<Vec<_>>();
+            let height = lines.len() as u16 + 2 /* borders */;
+            let popup_rect = centered_rect_length(area, 60, height);
+            let popup = Popup::default()
+                .title("Buffers")
+                .content(lines)
+                .footer(BUFFERS_HELP);
             frame.render_widget(popup, popup_rect);
         }
     }
@@ -217,16 +825,37 @@ impl App {
 
 impl Default for App {
     fn default() -> Self {
+        let (worker_tx, worker_rx) = mpsc::channel();
         App {
-            scroll_pos: 0,
             vertical_margin: 3,
             frame_size: (0, 0),
             show_help: false,
             show_fileinfo: false,
-            fileinfo: FileInfo::default(),
+            show_stats: false,
             quit: false,
             bytes_per_row: 16,
             blocksize: 8,
+            display: DisplayOptions::default(),
+            char_table: None,
+            export: ExportOptions::default(),
+            allow_write: false,
+            buffer: Buffer::default(),
+            buffers: vec![],
+            current: 0,
+            show_buffers: false,
+            show_mappings: false,
+            mappings_selected: 0,
+            show_members: false,
+            members_selected: 0,
+            show_bookmarks: false,
+            bookmarks_selected: 0,
+            buffers_selected: 0,
+            message: None,
+            show_diff: false,
+            pending_key: None,
+            command: None,
+            worker_tx,
+            worker_rx,
         }
     }
 }
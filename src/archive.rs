@@ -0,0 +1,779 @@
+use std::fmt;
+
+use log::debug;
+
+use crate::{
+    app::App,
+    compression::Compression,
+    file::{FileInfo, Source},
+};
+
+/// Size of the headers and records of tar
+const TAR_BLOCK: usize = 512;
+/// Size of the end of central directory record of zip, without comment
+const ZIP_EOCD_LEN: usize = 22;
+
+/// Archive formats whose members can be listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Zip and its derivatives (jar, apk, docx, ...)
+    Zip,
+    Tar,
+    /// New ASCII (`newc`, initramfs) and portable ASCII (`odc`) formats
+    Cpio,
+    /// Unix archives (static libraries, Debian packages)
+    Ar,
+}
+
+impl ArchiveFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::Cpio => "cpio",
+            ArchiveFormat::Ar => "ar",
+        }
+    }
+
+    /// Format of the archive in `content`, recognized by its magic bytes
+    pub fn detect(content: &[u8]) -> Option<Self> {
+        if content.starts_with(b"!<arch>\n") {
+            Some(ArchiveFormat::Ar)
+        } else if content.starts_with(b"070701")
+            || content.starts_with(b"070702")
+            || content.starts_with(b"070707")
+        {
+            Some(ArchiveFormat::Cpio)
+        } else if content.starts_with(b"PK\x03\x04") || zip_eocd(content).is_some() {
+            Some(ArchiveFormat::Zip)
+        } else if content.get(257..262) == Some(b"ustar")
+            || content.get(..TAR_BLOCK).is_some_and(is_tar_header)
+        {
+            Some(ArchiveFormat::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// How the data of a member is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Stored,
+    Compressed(Compression),
+    /// LZMA of zip, with its own header
+    ZipLzma,
+    /// Compression method of zip which cannot be extracted
+    Unsupported(u16),
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Method::Stored => write!(f, "stored"),
+            Method::Compressed(compression) => write!(f, "{compression}"),
+            Method::ZipLzma => write!(f, "lzma"),
+            Method::Unsupported(method) => write!(f, "method {method}"),
+        }
+    }
+}
+
+/// A file of an archive
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub name: String,
+    /// Offset of the header of the member in the archive
+    pub header: usize,
+    /// Offset of the data of the member, `size` bytes long
+    pub offset: usize,
+    pub size: usize,
+    /// Size of the extracted data
+    pub len: usize,
+    pub method: Method,
+    pub is_dir: bool,
+}
+
+impl Member {
+    /// Data of the member, decompressed
+    pub fn extract(&self, content: &[u8]) -> Result<Vec<u8>, String> {
+        let data = bytes(content, self.offset, self.size)
+            .map_err(|_| String::from("the data goes past the end of the archive"))?;
+        match self.method {
+            Method::Stored => Ok(data.to_vec()),
+            Method::Compressed(compression) => compression
+                .decompress(data)
+                .map_err(|err| format!("failed to decompress {compression}: {err}")),
+            Method::ZipLzma => {
+                // version and size of the properties, then the properties
+                // which start the header of `.lzma`, followed by the size
+                let props = data
+                    .get(4..9)
+                    .ok_or_else(|| String::from("truncated lzma header"))?;
+                let mut stream = props.to_vec();
+                stream.extend_from_slice(&(self.len as u64).to_le_bytes());
+                stream.extend_from_slice(&data[9..]);
+                Compression::Lzma
+                    .decompress(&stream)
+                    .map_err(|err| format!("failed to decompress lzma: {err}"))
+            }
+            Method::Unsupported(method) => Err(format!("unsupported compression method {method}")),
+        }
+    }
+}
+
+/// Members of an archive, in their order in the archive
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub format: ArchiveFormat,
+    pub members: Vec<Member>,
+}
+
+impl Archive {
+    /// Lists the members of the archive in `content`
+    pub fn parse(content: &[u8]) -> Result<Self, String> {
+        let format = ArchiveFormat::detect(content)
+            .ok_or_else(|| String::from("not a zip, tar, cpio or ar archive"))?;
+        let members = match format {
+            ArchiveFormat::Zip => zip_members(content),
+            ArchiveFormat::Tar => tar_members(content),
+            ArchiveFormat::Cpio => cpio_members(content),
+            ArchiveFormat::Ar => ar_members(content),
+        }
+        .map_err(|err| format!("invalid {} archive: {err}", format.name()))?;
+        debug!("{} archive of {} members", format.name(), members.len());
+        Ok(Archive { format, members })
+    }
+}
+
+/// `len` bytes at `offset`, or an error if the archive is truncated
+fn bytes(content: &[u8], offset: usize, len: usize) -> Result<&[u8], String> {
+    offset
+        .checked_add(len)
+        .and_then(|end| content.get(offset..end))
+        .ok_or_else(|| format!("truncated at {offset:#x}"))
+}
+
+fn u16_at(content: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = bytes(content, offset, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(content: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = bytes(content, offset, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn u64_at(content: &[u8], offset: usize) -> Result<u64, String> {
+    let mut value = [0; 8];
+    value.copy_from_slice(bytes(content, offset, 8)?);
+    Ok(u64::from_le_bytes(value))
+}
+
+/// Parses a number written in ASCII digits of `radix`, surrounded by spaces
+/// or NULs
+fn number(field: &[u8], radix: u32) -> Result<usize, String> {
+    let text = String::from_utf8_lossy(field);
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\0');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    usize::from_str_radix(text, radix).map_err(|_| format!("'{text}' is not a valid number"))
+}
+
+/// Text of a field, up to its first NUL
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Offset of the end of central directory record of a zip archive, which
+/// may be followed by a comment
+fn zip_eocd(content: &[u8]) -> Option<usize> {
+    let last = content.len().checked_sub(ZIP_EOCD_LEN)?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|&offset| content[offset..].starts_with(b"PK\x05\x06"))
+}
+
+fn zip_members(content: &[u8]) -> Result<Vec<Member>, String> {
+    let eocd = zip_eocd(content).ok_or("no end of central directory")?;
+    let mut count = u16_at(content, eocd + 10)? as u64;
+    let mut pos = u32_at(content, eocd + 16)? as u64;
+    // zip64 end of central directory, located right before
+    if (count == 0xffff || pos == 0xffff_ffff)
+        && let Some(locator) = eocd.checked_sub(20)
+        && content[locator..].starts_with(b"PK\x06\x07")
+    {
+        let zip64 = u64_at(content, locator + 8)? as usize;
+        let record = bytes(content, zip64, 56)?;
+        count = u64_at(record, 32)?;
+        pos = u64_at(record, 48)?;
+    }
+
+    let mut members = vec![];
+    let mut pos = pos as usize;
+    for _ in 0..count {
+        let entry = bytes(content, pos, 46)?;
+        if !entry.starts_with(b"PK\x01\x02") {
+            return Err(format!("expected a central directory header at {pos:#x}"));
+        }
+        let method = u16_at(entry, 10)?;
+        let mut size = u32_at(entry, 20)? as u64;
+        let mut len = u32_at(entry, 24)? as u64;
+        let name_len = u16_at(entry, 28)? as usize;
+        let extra_len = u16_at(entry, 30)? as usize;
+        let comment_len = u16_at(entry, 32)? as usize;
+        let mut header = u32_at(entry, 42)? as u64;
+        let name = String::from_utf8_lossy(bytes(content, pos + 46, name_len)?).into_owned();
+
+        // 64 bits values replacing the saturated ones, in this order
+        let extra = bytes(content, pos + 46 + name_len, extra_len)?;
+        let mut field = 0;
+        while field + 4 <= extra.len() {
+            let id = u16_at(extra, field)?;
+            let field_len = u16_at(extra, field + 2)? as usize;
+            if id == 0x0001 {
+                let mut value = field + 4;
+                for saturated in [&mut len, &mut size, &mut header] {
+                    if *saturated == 0xffff_ffff {
+                        *saturated = u64_at(extra, value)?;
+                        value += 8;
+                    }
+                }
+            }
+            field += 4 + field_len;
+        }
+
+        let header = header as usize;
+        let local = bytes(content, header, 30)?;
+        if !local.starts_with(b"PK\x03\x04") {
+            return Err(format!("expected a local file header at {header:#x}"));
+        }
+        let local_name_len = u16_at(local, 26)? as usize;
+        let local_extra_len = u16_at(local, 28)? as usize;
+        members.push(Member {
+            is_dir: name.ends_with('/'),
+            name,
+            header,
+            offset: header + 30 + local_name_len + local_extra_len,
+            size: size as usize,
+            len: len as usize,
+            method: match method {
+                0 => Method::Stored,
+                8 => Method::Compressed(Compression::Deflate),
+                12 => Method::Compressed(Compression::Bzip2),
+                14 => Method::ZipLzma,
+                93 => Method::Compressed(Compression::Zstd),
+                95 => Method::Compressed(Compression::Xz),
+                method => Method::Unsupported(method),
+            },
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(members)
+}
+
+/// Whether `header` is a tar header, from its checksum
+fn is_tar_header(header: &[u8]) -> bool {
+    let Ok(checksum) = number(&header[148..156], 8) else {
+        return false;
+    };
+    // the checksum field counts as spaces
+    let sum = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as usize)
+        .sum::<usize>();
+    header.iter().any(|&b| b != 0) && sum == checksum
+}
+
+/// Size field of tar, in octal or in base 256 for big files
+fn tar_size(field: &[u8]) -> Result<usize, String> {
+    if field[0] & 0x80 != 0 {
+        let value = field[1..]
+            .iter()
+            .fold(0u64, |value, &b| (value << 8) | b as u64);
+        return Ok(value as usize);
+    }
+    number(field, 8)
+}
+
+fn tar_members(content: &[u8]) -> Result<Vec<Member>, String> {
+    let mut members = vec![];
+    let mut pos = 0;
+    // extended headers (GNU long names, pax) describing the next member,
+    // from the first one, and the name they give
+    let mut extended: Option<usize> = None;
+    let mut long_name: Option<String> = None;
+    while pos + TAR_BLOCK <= content.len() {
+        let header = &content[pos..pos + TAR_BLOCK];
+        // the archive ends with empty blocks
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if !is_tar_header(header) {
+            return Err(format!("wrong checksum of the header at {pos:#x}"));
+        }
+        let size = tar_size(&header[124..136])?;
+        let offset = pos + TAR_BLOCK;
+        let data = bytes(content, offset, size)?;
+        let kind = header[156];
+        let next = offset + size.next_multiple_of(TAR_BLOCK);
+
+        match kind {
+            b'L' | b'K' | b'x' => {
+                extended.get_or_insert(pos);
+                if kind == b'L' {
+                    long_name = Some(text(data));
+                } else if kind == b'x' {
+                    // records `LEN path=NAME\n`
+                    let records = String::from_utf8_lossy(data);
+                    if let Some(path) = records
+                        .lines()
+                        .filter_map(|record| record.split_once(' ').map(|(_, record)| record))
+                        .find_map(|record| record.strip_prefix("path="))
+                    {
+                        long_name = Some(path.to_string());
+                    }
+                }
+            }
+            // global pax header
+            b'g' => {}
+            _ => {
+                let name = long_name.take().unwrap_or_else(|| {
+                    let name = text(&header[..100]);
+                    if &header[257..262] == b"ustar" && header[345] != 0 {
+                        format!("{}/{name}", text(&header[345..500]))
+                    } else {
+                        name
+                    }
+                });
+                let header = extended.take().unwrap_or(pos);
+                members.push(Member {
+                    is_dir: kind == b'5' || name.ends_with('/'),
+                    name,
+                    header,
+                    offset,
+                    size,
+                    len: size,
+                    method: Method::Stored,
+                });
+            }
+        }
+        pos = next;
+    }
+    Ok(members)
+}
+
+fn cpio_members(content: &[u8]) -> Result<Vec<Member>, String> {
+    let mut members = vec![];
+    let mut pos = 0;
+    loop {
+        let magic = bytes(content, pos, 6)?;
+        // fields of the `newc` format, in hexadecimal, and of the `odc`
+        // format, in octal
+        let (mode, name_len, size, name_start) = match magic {
+            b"070701" | b"070702" => {
+                let field = |i: usize| number(bytes(content, pos + 6 + 8 * i, 8)?, 16);
+                (field(1)?, field(11)?, field(6)?, pos + 110)
+            }
+            b"070707" => {
+                let field = |start: usize, len: usize| number(bytes(content, pos + start, len)?, 8);
+                (field(18, 6)?, field(59, 6)?, field(65, 11)?, pos + 76)
+            }
+            _ => return Err(format!("expected a header at {pos:#x}")),
+        };
+        let name = text(bytes(content, name_start, name_len)?);
+        let aligned = |offset: usize| match magic {
+            b"070707" => offset,
+            _ => offset.next_multiple_of(4),
+        };
+        let offset = aligned(name_start + name_len);
+        bytes(content, offset, size)?;
+        pos = aligned(offset + size);
+
+        if name == "TRAILER!!!" {
+            // initramfs made of several archives, separated with zeros
+            let rest = content.get(pos..).unwrap_or_default();
+            match rest.iter().position(|&b| b != 0) {
+                Some(padding) if rest[padding..].starts_with(b"0707") => {
+                    pos += padding;
+                    continue;
+                }
+                _ => break,
+            }
+        }
+        members.push(Member {
+            name,
+            header: name_start - if magic == b"070707" { 76 } else { 110 },
+            offset,
+            size,
+            len: size,
+            method: Method::Stored,
+            is_dir: mode & 0o170000 == 0o040000,
+        });
+    }
+    Ok(members)
+}
+
+fn ar_members(content: &[u8]) -> Result<Vec<Member>, String> {
+    let mut members = vec![];
+    // names of the GNU format longer than 15 characters
+    let mut long_names: &[u8] = &[];
+    let mut pos = 8;
+    while pos < content.len() {
+        let header = bytes(content, pos, 60)?;
+        if &header[58..60] != b"`\n" {
+            return Err(format!("expected a header at {pos:#x}"));
+        }
+        let raw_name = String::from_utf8_lossy(&header[..16])
+            .trim_end()
+            .to_string();
+        let mut offset = pos + 60;
+        let mut size = number(&header[48..58], 10)?;
+        let data = bytes(content, offset, size)?;
+
+        let name = if raw_name == "//" {
+            long_names = data;
+            None
+        } else if let Some(len) = raw_name.strip_prefix("#1/") {
+            // BSD format, the name starts the data
+            let len = number(len.as_bytes(), 10)?;
+            let name = text(bytes(content, offset, len)?);
+            offset += len;
+            size = size.saturating_sub(len);
+            Some(name)
+        } else if let Some(index) = raw_name.strip_prefix('/').filter(|index| !index.is_empty()) {
+            let start = number(index.as_bytes(), 10)?;
+            let names = long_names.get(start..).unwrap_or_default();
+            let end = names
+                .iter()
+                .position(|&b| b == b'\n')
+                .unwrap_or(names.len());
+            Some(
+                String::from_utf8_lossy(&names[..end])
+                    .trim_end_matches('/')
+                    .to_string(),
+            )
+        } else if raw_name == "/" || raw_name == "__.SYMDEF" {
+            Some(format!("{raw_name} (symbol table)"))
+        } else {
+            Some(raw_name.trim_end_matches('/').to_string())
+        };
+
+        if let Some(name) = name {
+            members.push(Member {
+                name,
+                header: pos,
+                offset,
+                size,
+                len: size,
+                method: Method::Stored,
+                is_dir: false,
+            });
+        }
+        pos = (pos + 60 + number(&header[48..58], 10)?).next_multiple_of(2);
+    }
+    Ok(members)
+}
+
+impl App {
+    /// Lists the members of the displayed archive in a popup
+    pub fn show_archive(&mut self) {
+        if self.buffer.archive.is_none() {
+            match Archive::parse(&self.buffer.fileinfo.content) {
+                Ok(archive) => self.buffer.archive = Some(archive),
+                Err(err) => {
+                    self.message = Some(err);
+                    return;
+                }
+            }
+        }
+        self.members_selected = 0;
+        self.show_members = true;
+    }
+
+    /// Selects the header and the data of the member at `idx`, the cursor
+    /// being on the header
+    pub fn jump_to_member(&mut self, idx: usize) {
+        let Some(member) = self
+            .buffer
+            .archive
+            .as_ref()
+            .and_then(|a| a.members.get(idx))
+        else {
+            return;
+        };
+        let (header, end) = (member.header, member.offset.saturating_add(member.size));
+        self.move_cursor_to_offset(end.saturating_sub(1));
+        self.buffer.selection_anchor = Some(self.buffer.cursor);
+        self.move_cursor_to_offset(header);
+    }
+
+    /// Opens the extracted data of the member at `idx` in a new buffer
+    pub fn open_member(&mut self, idx: usize) -> Result<(), String> {
+        let Some(member) = self
+            .buffer
+            .archive
+            .as_ref()
+            .and_then(|a| a.members.get(idx))
+        else {
+            return Ok(());
+        };
+        let fileinfo = &self.buffer.fileinfo;
+        let data = member
+            .extract(&fileinfo.content)
+            .map_err(|err| format!("failed to extract '{}': {err}", member.name))?;
+        let fileinfo = FileInfo {
+            path: fileinfo.path.clone(),
+            name: format!("{} [{}]", fileinfo.name, member.name),
+            size: data.len(),
+            content: data.into(),
+            source: Source::Member {
+                offset: member.header,
+            },
+            ..FileInfo::default()
+        };
+        self.open_fileinfo(fileinfo);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(archive: &Archive) -> Vec<&str> {
+        archive.members.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    fn extract(content: &[u8], member: &Member) -> Vec<u8> {
+        member.extract(content).unwrap()
+    }
+
+    /// Zip of stored files, with a zip64 end of central directory if `zip64`
+    fn zip(files: &[(&str, &[u8])], zip64: bool) -> Vec<u8> {
+        let mut content = vec![];
+        let mut directory = vec![];
+        for (name, data) in files {
+            let header = content.len() as u32;
+            content.extend(b"PK\x03\x04");
+            content.extend([0; 14]);
+            content.extend((data.len() as u32).to_le_bytes());
+            content.extend((data.len() as u32).to_le_bytes());
+            content.extend((name.len() as u16).to_le_bytes());
+            content.extend([0; 2]);
+            content.extend(name.as_bytes());
+            content.extend(*data);
+
+            directory.extend(b"PK\x01\x02");
+            directory.extend([0; 16]);
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(header.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let start = content.len();
+        content.extend(&directory);
+        let (count, pos) = if zip64 {
+            let record = content.len() as u64;
+            content.extend(b"PK\x06\x06");
+            content.extend([0; 28]);
+            content.extend((files.len() as u64).to_le_bytes());
+            content.extend([0; 8]);
+            content.extend((start as u64).to_le_bytes());
+            content.extend(b"PK\x06\x07");
+            content.extend([0; 4]);
+            content.extend(record.to_le_bytes());
+            content.extend([0; 4]);
+            (0xffff, 0xffff_ffff)
+        } else {
+            (files.len() as u16, start as u32)
+        };
+        content.extend(b"PK\x05\x06");
+        content.extend([0; 6]);
+        content.extend(count.to_le_bytes());
+        content.extend((directory.len() as u32).to_le_bytes());
+        content.extend(pos.to_le_bytes());
+        content.extend([0; 2]);
+        content
+    }
+
+    /// Header and data of a tar entry of type `kind`
+    fn tar_entry(name: &str, kind: u8, data: &[u8]) -> Vec<u8> {
+        let mut header = vec![0; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[148..156].copy_from_slice(b"        ");
+        let sum = header.iter().map(|&b| b as usize).sum::<usize>();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        header.extend(data);
+        header.resize(header.len().next_multiple_of(TAR_BLOCK), 0);
+        header
+    }
+
+    fn newc_entry(name: &str, mode: usize, data: &[u8]) -> Vec<u8> {
+        let mut fields = [0; 13];
+        fields[1] = mode;
+        fields[6] = data.len();
+        fields[11] = name.len() + 1;
+        let mut entry = b"070701".to_vec();
+        for field in fields {
+            entry.extend(format!("{field:08x}").as_bytes());
+        }
+        entry.extend(name.as_bytes());
+        entry.push(0);
+        entry.resize(entry.len().next_multiple_of(4), 0);
+        entry.extend(data);
+        entry.resize(entry.len().next_multiple_of(4), 0);
+        entry
+    }
+
+    fn odc_entry(name: &str, mode: usize, data: &[u8]) -> Vec<u8> {
+        let mut entry = b"070707".to_vec();
+        entry.extend(format!("{:012}{mode:06o}{:035}", 0, 0).as_bytes());
+        entry.extend(format!("{:06o}{:011o}", name.len() + 1, data.len()).as_bytes());
+        entry.extend(name.as_bytes());
+        entry.push(0);
+        entry.extend(data);
+        entry
+    }
+
+    fn ar_entry(name: &str, data: &[u8]) -> Vec<u8> {
+        let mut entry = format!("{name:<16}{:<32}{:<10}`\n", "", data.len()).into_bytes();
+        entry.extend(data);
+        if entry.len() % 2 == 1 {
+            entry.push(b'\n');
+        }
+        entry
+    }
+
+    #[test]
+    fn zip_members() {
+        for zip64 in [false, true] {
+            let content = zip(&[("dir/", b""), ("dir/a.txt", b"hello")], zip64);
+            let archive = Archive::parse(&content).unwrap();
+            assert_eq!(archive.format, ArchiveFormat::Zip);
+            assert_eq!(names(&archive), ["dir/", "dir/a.txt"]);
+            assert!(archive.members[0].is_dir);
+            assert_eq!(archive.members[1].header, 34);
+            assert_eq!(extract(&content, &archive.members[1]), b"hello");
+        }
+    }
+
+    #[test]
+    fn zip_offsets_past_the_end() {
+        let mut content = zip(&[("a", b"x")], true);
+        // zip64 end of central directory at the end of the address space
+        let locator = content.len() - ZIP_EOCD_LEN - 20;
+        content[locator + 8..locator + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Archive::parse(&content).is_err());
+
+        let member = Member {
+            name: String::from("a"),
+            header: 0,
+            offset: usize::MAX,
+            size: 2,
+            len: 2,
+            method: Method::Stored,
+            is_dir: false,
+        };
+        assert!(member.extract(&content).is_err());
+    }
+
+    #[test]
+    fn tar_long_names() {
+        let long = "d/".repeat(60) + "long.txt";
+        let content = [
+            tar_entry("short.txt", b'0', b"short"),
+            // GNU long name
+            tar_entry("././@LongLink", b'L', format!("{long}\0").as_bytes()),
+            tar_entry("truncated", b'0', b"gnu"),
+            // pax extended header, the record length counting itself
+            tar_entry("PaxHeaders/x", b'x', b"21 path=pax/name.txt\n"),
+            tar_entry("truncated", b'0', b"pax"),
+            tar_entry("dir/", b'5', b""),
+            vec![0; 2 * TAR_BLOCK],
+        ]
+        .concat();
+        let archive = Archive::parse(&content).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Tar);
+        assert_eq!(
+            names(&archive),
+            ["short.txt", long.as_str(), "pax/name.txt", "dir/"]
+        );
+        // the header of a member is its first extended header
+        assert_eq!(archive.members[1].header, 2 * TAR_BLOCK);
+        assert_eq!(archive.members[2].header, 6 * TAR_BLOCK);
+        assert_eq!(extract(&content, &archive.members[1]), b"gnu");
+        assert_eq!(extract(&content, &archive.members[2]), b"pax");
+        assert!(archive.members[3].is_dir);
+    }
+
+    #[test]
+    fn cpio_newc() {
+        let content = [
+            newc_entry("dir", 0o040755, b""),
+            newc_entry("dir/file", 0o100644, b"abcde"),
+            newc_entry("TRAILER!!!", 0, b""),
+            // initramfs of several archives
+            vec![0; 8],
+            newc_entry("second", 0o100644, b"xyz"),
+            newc_entry("TRAILER!!!", 0, b""),
+        ]
+        .concat();
+        let archive = Archive::parse(&content).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Cpio);
+        assert_eq!(names(&archive), ["dir", "dir/file", "second"]);
+        assert!(archive.members[0].is_dir);
+        assert_eq!(extract(&content, &archive.members[1]), b"abcde");
+        assert_eq!(extract(&content, &archive.members[2]), b"xyz");
+    }
+
+    #[test]
+    fn cpio_odc() {
+        let content = [
+            odc_entry("file", 0o100644, b"odc data"),
+            odc_entry("TRAILER!!!", 0, b""),
+        ]
+        .concat();
+        let archive = Archive::parse(&content).unwrap();
+        assert_eq!(names(&archive), ["file"]);
+        assert_eq!(archive.members[0].offset, 76 + 5);
+        assert_eq!(extract(&content, &archive.members[0]), b"odc data");
+    }
+
+    #[test]
+    fn ar_gnu() {
+        let long = "a_rather_long_name.o";
+        let content = [
+            b"!<arch>\n".to_vec(),
+            ar_entry("/", b"\0\0\0\0"),
+            ar_entry("//", format!("{long}/\n").as_bytes()),
+            ar_entry("short.o/", b"odd"),
+            ar_entry("/0", b"long"),
+        ]
+        .concat();
+        let archive = Archive::parse(&content).unwrap();
+        assert_eq!(archive.format, ArchiveFormat::Ar);
+        assert_eq!(names(&archive), ["/ (symbol table)", "short.o", long]);
+        assert_eq!(extract(&content, &archive.members[1]), b"odd");
+        assert_eq!(extract(&content, &archive.members[2]), b"long");
+    }
+
+    #[test]
+    fn ar_bsd() {
+        let long = "a_rather_long_name.o";
+        let mut data = long.as_bytes().to_vec();
+        data.extend(b"bsd");
+        let content = [b"!<arch>\n".to_vec(), ar_entry("#1/20", &data)].concat();
+        let archive = Archive::parse(&content).unwrap();
+        assert_eq!(names(&archive), [long]);
+        assert_eq!(extract(&content, &archive.members[0]), b"bsd");
+    }
+}
@@ -0,0 +1,309 @@
+use std::fs;
+use std::path::PathBuf;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+
+/// Directory of the saved bookmarks, in the data directory of the user
+const BOOKMARKS_DIR: &str = "hexplore/bookmarks";
+
+/// A position of a file worth coming back to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Letter of a mark (`ma`), or any name
+    pub name: String,
+    pub offset: usize,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String,
+}
+
+impl Bookmark {
+    /// Character shown in the address column on the line of the bookmark
+    pub fn marker(&self) -> char {
+        let mut chars = self.name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => '*',
+        }
+    }
+}
+
+/// Bookmarks of a buffer, sorted by offset. They are saved under the SHA-256
+/// of the content, so they are found again whatever the path of the file.
+#[derive(Debug)]
+pub struct Bookmarks {
+    pub list: Vec<Bookmark>,
+    /// SHA-256 of the content, once computed
+    sha256: Option<String>,
+    /// SHA-256 of the content before it changed, until the new one is known
+    former: Option<String>,
+    /// Names of the bookmarks set before the hash was known, saved with it
+    unsaved: Vec<String>,
+    /// Directory of the saved bookmarks, `None` if there is no data directory
+    dir: Option<PathBuf>,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        Bookmarks {
+            list: vec![],
+            sha256: None,
+            former: None,
+            unsaved: vec![],
+            dir: dirs::data_dir().map(|dir| dir.join(BOOKMARKS_DIR)),
+        }
+    }
+}
+
+impl Bookmarks {
+    pub fn get(&self, name: &str) -> Option<&Bookmark> {
+        self.list.iter().find(|bookmark| bookmark.name == name)
+    }
+
+    /// Whether the bookmarks wait for the hash of the content to be saved
+    pub fn is_queued(&self) -> bool {
+        self.sha256.is_none()
+    }
+
+    /// Adds a bookmark, replacing the one with the same name
+    pub fn set(&mut self, bookmark: Bookmark) -> Result<(), String> {
+        self.list.retain(|b| b.name != bookmark.name);
+        if self.is_queued() {
+            self.unsaved.push(bookmark.name.clone());
+        }
+        self.list.push(bookmark);
+        self.list.sort_by_key(|b| b.offset);
+        self.save()
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Result<(), String> {
+        if idx < self.list.len() {
+            let bookmark = self.list.remove(idx);
+            self.unsaved.retain(|name| *name != bookmark.name);
+        }
+        self.save()
+    }
+
+    /// Forgets the hash of the content, which changed. The bookmarks are kept
+    /// until the new hash is known, in case the content is the same.
+    pub fn unload(&mut self) {
+        if let Some(sha256) = self.sha256.take() {
+            self.former = Some(sha256);
+        }
+    }
+
+    /// Adds the bookmarks saved for the content of hash `sha256` to the ones
+    /// set before it was computed, which are then saved as well. The
+    /// bookmarks of a former content are dropped, being saved under its hash.
+    pub fn load(&mut self, sha256: String) -> Result<(), String> {
+        if self.former.take().is_some_and(|former| former != sha256) {
+            let unsaved = &self.unsaved;
+            self.list.retain(|b| unsaved.contains(&b.name));
+        }
+        let path = self.path(&sha256);
+        self.sha256 = Some(sha256);
+        let unsaved = std::mem::take(&mut self.unsaved);
+        let Some(path) = path else {
+            return Ok(());
+        };
+        let saved = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str::<Vec<Bookmark>>(&json)
+                .map_err(|err| format!("invalid bookmarks in '{}': {err}", path.display()))?,
+            Err(_) => vec![],
+        };
+        debug!("{} bookmarks loaded from '{}'", saved.len(), path.display());
+
+        for bookmark in saved {
+            if self.get(&bookmark.name).is_none() {
+                self.list.push(bookmark);
+            }
+        }
+        self.list.sort_by_key(|b| b.offset);
+        if unsaved.is_empty() {
+            Ok(())
+        } else {
+            self.save()
+        }
+    }
+
+    /// File of the bookmarks of the content of hash `sha256`
+    fn path(&self, sha256: &str) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{sha256}.json")))
+    }
+
+    /// Writes the bookmarks to their file, if the hash of the content is
+    /// known
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = self.sha256.as_deref().and_then(|sha256| self.path(sha256)) else {
+            return Ok(());
+        };
+        let err = |err: String| {
+            format!(
+                "failed to save the bookmarks to '{}': {err}",
+                path.display()
+            )
+        };
+        if self.list.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(err(e.to_string())),
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| err(e.to_string()))?;
+        }
+        let json = serde_json::to_string_pretty(&self.list).map_err(|e| err(e.to_string()))?;
+        fs::write(&path, json).map_err(|e| err(e.to_string()))
+    }
+}
+
+impl App {
+    /// Bookmarks the offset of the cursor as `name`
+    pub fn set_bookmark(&mut self, name: &str, comment: &str) {
+        let Some(offset) = self.cursor_offset() else {
+            self.message = Some(String::from("the cursor is not on a byte of the file"));
+            return;
+        };
+        let bookmark = Bookmark {
+            name: name.to_string(),
+            offset,
+            comment: comment.to_string(),
+        };
+        let bookmarks = &mut self.buffer.bookmarks;
+        self.message = Some(match bookmarks.set(bookmark) {
+            Ok(()) if bookmarks.is_queued() => {
+                format!("bookmark '{name}' set at {offset:#x}, saved once the SHA-256 is computed")
+            }
+            Ok(()) => format!("bookmark '{name}' set at {offset:#x}"),
+            Err(err) => err,
+        });
+    }
+
+    /// Moves the cursor to the bookmark `name`
+    pub fn jump_to_bookmark(&mut self, name: &str) {
+        match self.buffer.bookmarks.get(name) {
+            Some(bookmark) => self.move_cursor_to_offset(bookmark.offset),
+            None => self.message = Some(format!("no bookmark '{name}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bookmarks saved in a directory of their own
+    fn bookmarks(test: &str) -> Bookmarks {
+        let dir =
+            std::env::temp_dir().join(format!("hexplore-bookmarks-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Bookmarks {
+            dir: Some(dir),
+            ..Bookmarks::default()
+        }
+    }
+
+    fn bookmark(name: &str, offset: usize) -> Bookmark {
+        Bookmark {
+            name: name.to_string(),
+            offset,
+            comment: String::new(),
+        }
+    }
+
+    fn names(bookmarks: &Bookmarks) -> Vec<&str> {
+        bookmarks.list.iter().map(|b| b.name.as_str()).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut bookmarks = bookmarks("round_trip");
+        bookmarks.load(String::from("aaaa")).unwrap();
+        bookmarks.set(bookmark("b", 0x20)).unwrap();
+        bookmarks
+            .set(Bookmark {
+                comment: String::from("header"),
+                ..bookmark("a", 0x10)
+            })
+            .unwrap();
+        assert!(bookmarks.dir.as_ref().unwrap().join("aaaa.json").is_file());
+
+        // found again under the same hash only
+        let mut other = Bookmarks {
+            dir: bookmarks.dir.clone(),
+            ..Bookmarks::default()
+        };
+        other.load(String::from("aaaa")).unwrap();
+        assert_eq!(other.list, bookmarks.list);
+        assert_eq!(other.get("a").unwrap().comment, "header");
+        let mut other = Bookmarks {
+            dir: bookmarks.dir.clone(),
+            ..Bookmarks::default()
+        };
+        other.load(String::from("bbbb")).unwrap();
+        assert!(other.list.is_empty());
+
+        // the file is removed with the last bookmark
+        bookmarks.remove(0).unwrap();
+        bookmarks.remove(0).unwrap();
+        assert!(!bookmarks.dir.as_ref().unwrap().join("aaaa.json").exists());
+        let _ = fs::remove_dir_all(bookmarks.dir.unwrap());
+    }
+
+    #[test]
+    fn set_before_the_hash_is_saved_with_it() {
+        let mut saved = bookmarks("queued");
+        saved.load(String::from("aaaa")).unwrap();
+        saved.set(bookmark("a", 0x10)).unwrap();
+
+        let mut bookmarks = Bookmarks {
+            dir: saved.dir.clone(),
+            ..Bookmarks::default()
+        };
+        bookmarks.set(bookmark("b", 0x8)).unwrap();
+        assert!(bookmarks.is_queued());
+        bookmarks.load(String::from("aaaa")).unwrap();
+        assert!(!bookmarks.is_queued());
+        assert_eq!(names(&bookmarks), ["b", "a"]);
+
+        let mut other = Bookmarks {
+            dir: saved.dir.clone(),
+            ..Bookmarks::default()
+        };
+        other.load(String::from("aaaa")).unwrap();
+        assert_eq!(other.list, bookmarks.list);
+        let _ = fs::remove_dir_all(bookmarks.dir.unwrap());
+    }
+
+    #[test]
+    fn changed_content_drops_former_bookmarks() {
+        let mut bookmarks = bookmarks("changed");
+        bookmarks.load(String::from("aaaa")).unwrap();
+        bookmarks.set(bookmark("a", 0x10)).unwrap();
+
+        // same content, e.g. touched
+        bookmarks.unload();
+        bookmarks.load(String::from("aaaa")).unwrap();
+        assert_eq!(names(&bookmarks), ["a"]);
+
+        // set between the change and the new hash, kept
+        bookmarks.unload();
+        bookmarks.set(bookmark("b", 0x8)).unwrap();
+        bookmarks.load(String::from("bbbb")).unwrap();
+        assert_eq!(names(&bookmarks), ["b"]);
+
+        // the former ones stay saved under the former hash
+        let mut other = Bookmarks {
+            dir: bookmarks.dir.clone(),
+            ..Bookmarks::default()
+        };
+        other.load(String::from("aaaa")).unwrap();
+        assert_eq!(names(&other), ["a"]);
+        other.unload();
+        other.load(String::from("bbbb")).unwrap();
+        assert_eq!(names(&other), ["b"]);
+        let _ = fs::remove_dir_all(bookmarks.dir.unwrap());
+    }
+}
@@ -0,0 +1,461 @@
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+use crate::{
+    addressing::{AddressMap, AddressSpace, Segment},
+    archive::Archive,
+    bookmarks::Bookmarks,
+    diff::Diff,
+    file::{Analysis, FileInfo, Source},
+    firmware::ImageFormat,
+    hash::{self, HashAlgorithm, Hashes},
+    minimap::Minimap,
+    regions::Regions,
+    stats::StatsJob,
+    strings::StringsPanel,
+    watch,
+    worker::{WorkerMsg, WorkerSender},
+};
+
+/// How long changed bytes stay highlighted after a reload
+const CHANGE_HIGHLIGHT: Duration = Duration::from_secs(2);
+
+/// An opened file along with its own view state (position, selection,
+/// analyses, ...)
+#[derive(Debug, Default)]
+pub struct Buffer {
+    pub fileinfo: FileInfo,
+    pub diff: Option<Diff>,
+    pub scroll_pos: usize,
+    pub cursor: usize,
+    pub selection_anchor: Option<usize>,
+    pub stats: StatsJob,
+    pub hashes: Hashes,
+    /// Hash job computing the SHA-256 of the whole content, shown in the
+    /// file details and naming the saved bookmarks
+    sha256_job: Option<u64>,
+    pub strings: StringsPanel,
+    pub minimap: Minimap,
+    /// Members of the archive, listed when the member list is first opened
+    pub archive: Option<Archive>,
+    pub bookmarks: Bookmarks,
+    /// Labeled ranges of bytes, from the project file of the opened file
+    pub regions: Regions,
+    /// Virtual addresses of the content, and whether they are displayed
+    /// instead of file offsets
+    pub address_map: AddressMap,
+    pub address_space: AddressSpace,
+    /// Addresses covered by the view in the virtual address space, from the
+    /// lowest mapped one to the end of the highest, gaps included. `None`
+    /// when the view is made of file offsets.
+    span: Option<Range<usize>>,
+    /// Keeps the cursor at the end of the file when it grows, like `tail -f`
+    pub follow: bool,
+    /// Ranges changed by the last reload, and when it happened
+    pub changes: Vec<Range<usize>>,
+    pub changed_at: Option<Instant>,
+    /// Sender of the background threads working on the current content
+    pub tx: WorkerSender,
+}
+
+impl Buffer {
+    /// Builds the buffer and starts its background analyses, once the file
+    /// is read if it is still to be
+    pub fn new(fileinfo: FileInfo, diff: Option<Diff>, tx: WorkerSender) -> Self {
+        let regions = match fileinfo.source {
+            Source::File => Regions::load(&fileinfo.path),
+            _ => Regions::default(),
+        };
+        let mut buffer = Buffer::default();
+        buffer.minimap = Minimap::new(fileinfo.size);
+        buffer.regions = regions;
+        buffer.strings = StringsPanel::new();
+        buffer.fileinfo = fileinfo;
+        buffer.diff = diff;
+        buffer.tx = tx;
+        if buffer.fileinfo.loading.is_some() {
+            buffer.fileinfo.spawn_loader(buffer.tx.clone());
+        } else {
+            buffer.start();
+        }
+        buffer
+    }
+
+    /// Lays out the content and starts its background analyses
+    fn start(&mut self) {
+        // a process and a firmware image are browsed with their addresses
+        let segments = self
+            .fileinfo
+            .mappings
+            .iter()
+            .filter(|mapping| mapping.len > 0)
+            .map(|mapping| Segment {
+                offset: mapping.offset,
+                len: mapping.len,
+                address: mapping.start,
+            })
+            .chain(self.fileinfo.segments.iter().copied())
+            .collect::<Vec<_>>();
+        if !segments.is_empty() {
+            self.set_address_map(AddressMap::new(segments));
+        }
+        self.spawn_analyses();
+        self.spawn_watcher();
+    }
+
+    /// Watches the file for changes, to reload it
+    fn spawn_watcher(&self) {
+        // the diff is computed once, compared files are not reloaded, nor
+        // decoded firmware images
+        if self.fileinfo.source == Source::File
+            && self.diff.is_none()
+            && self.fileinfo.format == ImageFormat::Raw
+        {
+            watch::spawn_watcher(
+                self.fileinfo.path.clone(),
+                self.fileinfo.content.clone(),
+                self.tx.clone(),
+            );
+        }
+    }
+
+    /// Starts the analyses of the whole content: the minimap, the file type
+    /// and the SHA-256, which is a hash job like the ones of the file details
+    fn spawn_analyses(&mut self) {
+        let tx = self.tx.clone();
+        let content = self.fileinfo.content.clone();
+        self.minimap = Minimap {
+            visible: self.minimap.visible,
+            mode: self.minimap.mode,
+            ..Minimap::new(self.fileinfo.size)
+        };
+        self.minimap.spawn_worker(content.clone(), tx.clone());
+        #[cfg(target_os = "linux")]
+        self.fileinfo.spawn_filetype(tx.clone());
+
+        let range = 0..self.fileinfo.size;
+        let algorithms = vec![HashAlgorithm::Sha256];
+        let job = self.hashes.start_job(range.clone(), &algorithms);
+        self.sha256_job = Some(job);
+        hash::spawn_worker(job, content, range, algorithms, tx);
+    }
+
+    /// Opens `filename`, compared with `diff_with` if any. `allow_write` lets
+    /// `:save` write over `filename` only.
+    pub fn open(
+        filename: &str,
+        diff_with: Option<&str>,
+        align: bool,
+        allow_write: bool,
+        tx: WorkerSender,
+    ) -> io::Result<Self> {
+        // the diff is computed right away, from the content of both files
+        let mut fileinfo = match diff_with {
+            Some(_) => FileInfo::new(filename)?,
+            None => FileInfo::open(filename)?,
+        };
+        fileinfo.writable = allow_write;
+        debug!("'{filename}' information retrieved");
+        let diff = match diff_with {
+            Some(other) => {
+                let other = FileInfo::new(other)?;
+                let diff = if align {
+                    Diff::new_aligned(&fileinfo.content, other)
+                } else {
+                    Diff::new(&fileinfo.content, other)
+                };
+                debug!("diff with '{}': {}", diff.other.name, diff.summary());
+                Some(diff)
+            }
+            None => None,
+        };
+
+        Ok(Buffer::new(fileinfo, diff, tx))
+    }
+
+    /// Uses `address_map` for virtual addresses, and displays them
+    pub fn set_address_map(&mut self, address_map: AddressMap) {
+        let address_space = if address_map.is_empty() {
+            AddressSpace::FileOffset
+        } else {
+            AddressSpace::Virtual
+        };
+        self.set_view(address_map, address_space);
+    }
+
+    /// Switches between file offsets and virtual addresses
+    pub fn toggle_address_space(&mut self) {
+        self.set_view(self.address_map.clone(), self.address_space.toggle());
+    }
+
+    /// Lays out the view in `address_space`, the cursor and the selection
+    /// staying on the same bytes when they are still shown
+    fn set_view(&mut self, address_map: AddressMap, address_space: AddressSpace) {
+        let cursor = self.offset_at(self.cursor);
+        let anchor = self.selection_anchor.and_then(|pos| self.offset_at(pos));
+
+        self.address_map = address_map;
+        self.address_space = address_space;
+        self.update_span();
+
+        self.cursor = cursor
+            .and_then(|offset| self.position_of(offset))
+            .unwrap_or(0);
+        self.selection_anchor = anchor.and_then(|offset| self.position_of(offset));
+    }
+
+    fn update_span(&mut self) {
+        let size = self.fileinfo.size;
+        self.span = match self.address_space {
+            // the aligned diff view has its own layout
+            AddressSpace::Virtual if self.diff.is_none() => {
+                let segments = self.segments();
+                let start = segments.iter().map(|segment| segment.address).min();
+                let end = segments
+                    .iter()
+                    .map(|segment| segment.address + segment.len)
+                    .max();
+                start.zip(end).map(|(start, end)| start..end)
+            }
+            _ => None,
+        };
+        debug!("view of {size} bytes over the addresses {:x?}", self.span);
+    }
+
+    /// Addresses covered by the view, `None` for file offsets
+    pub fn span(&self) -> Option<&Range<usize>> {
+        self.span.as_ref()
+    }
+
+    /// Segments of the address map, cut to the size of the file
+    pub fn segments(&self) -> Vec<Segment> {
+        let size = self.fileinfo.size;
+        self.address_map
+            .segments()
+            .iter()
+            .filter(|segment| segment.offset < size)
+            .map(|segment| Segment {
+                len: std::cmp::min(segment.len, size - segment.offset),
+                ..*segment
+            })
+            .collect()
+    }
+
+    /// Number of positions of the view, outside of diff mode
+    pub fn view_len(&self) -> usize {
+        match &self.span {
+            Some(span) => span.len(),
+            None => self.fileinfo.size,
+        }
+    }
+
+    /// File offset of the byte at `pos` in the view, `None` in a gap of the
+    /// virtual addresses
+    pub fn offset_at(&self, pos: usize) -> Option<usize> {
+        match &self.span {
+            Some(span) => self
+                .address_map
+                .offset_of(span.start + pos)
+                .filter(|&offset| offset < self.fileinfo.size),
+            None => Some(pos),
+        }
+    }
+
+    /// Position of the byte at `offset` in the view, `None` if it is not
+    /// mapped to a virtual address
+    pub fn position_of(&self, offset: usize) -> Option<usize> {
+        match &self.span {
+            Some(span) => self
+                .address_map
+                .address_of(offset)
+                .filter(|address| span.contains(address))
+                .map(|address| address - span.start),
+            None => Some(offset),
+        }
+    }
+
+    /// Smallest range of file offsets holding the mapped bytes of the
+    /// `positions` of the view
+    pub fn offset_range(&self, positions: Range<usize>) -> Range<usize> {
+        let Some(span) = &self.span else {
+            return positions;
+        };
+        let start = span.start + positions.start;
+        let end = span.start + positions.end;
+        self.segments()
+            .iter()
+            .filter(|segment| segment.address < end && start < segment.address + segment.len)
+            .map(|segment| {
+                let first = std::cmp::max(start, segment.address) - segment.address;
+                let last = std::cmp::min(end, segment.address + segment.len) - segment.address;
+                segment.offset + first..segment.offset + last
+            })
+            .reduce(|a, b| std::cmp::min(a.start, b.start)..std::cmp::max(a.end, b.end))
+            .unwrap_or(0..0)
+    }
+
+    /// Positions in the view of the start of each mapped range, sorted
+    pub fn range_starts(&self) -> Vec<usize> {
+        let mut starts = self
+            .segments()
+            .iter()
+            .filter_map(|segment| self.position_of(segment.offset))
+            .collect::<Vec<_>>();
+        starts.sort_unstable();
+        starts.dedup();
+        starts
+    }
+
+    /// Address of `offset` in the displayed address space, `None` if it is
+    /// not mapped
+    pub fn address_of(&self, offset: usize) -> Option<usize> {
+        match self.address_space {
+            AddressSpace::FileOffset => Some(offset),
+            AddressSpace::Virtual => self.address_map.address_of(offset),
+        }
+    }
+
+    /// Offset of `address` of the displayed address space, `None` if it is
+    /// not mapped
+    pub fn offset_of(&self, address: usize) -> Option<usize> {
+        match self.address_space {
+            AddressSpace::FileOffset => Some(address),
+            AddressSpace::Virtual => self.address_map.offset_of(address),
+        }
+    }
+
+    /// Highest address displayed, sizing the address column
+    pub fn last_address(&self) -> usize {
+        let size = self.fileinfo.size;
+        match self.address_space {
+            AddressSpace::FileOffset => size,
+            AddressSpace::Virtual => std::cmp::max(self.address_map.last_address(size), size),
+        }
+    }
+
+    /// Replaces the content after the file changed on disk, and computes
+    /// again what depended on it
+    fn reload(&mut self, content: Arc<[u8]>, changes: Vec<Range<usize>>) {
+        // the threads working on the former content stop
+        self.tx = self.tx.renew();
+        let cursor = self.offset_at(self.cursor);
+        let anchor = self.selection_anchor.and_then(|pos| self.offset_at(pos));
+        let fileinfo = &mut self.fileinfo;
+        fileinfo.size = content.len();
+        fileinfo.content = content;
+        fileinfo.sha256 = Analysis::default();
+        self.bookmarks.unload();
+        #[cfg(target_os = "linux")]
+        {
+            fileinfo.filetype = Analysis::default();
+        }
+        self.hashes.clear();
+        self.spawn_analyses();
+        self.spawn_watcher();
+        if self.strings.strings.is_some() {
+            self.strings
+                .spawn_worker(self.fileinfo.content.clone(), self.tx.clone());
+        }
+        self.stats.clear();
+        self.archive = None;
+
+        // the span follows the size of the file
+        self.update_span();
+        let last = self.view_len().saturating_sub(1);
+        let position = |offset: Option<usize>| {
+            let offset = std::cmp::min(offset?, self.fileinfo.size.saturating_sub(1));
+            self.position_of(offset).map(|pos| pos.min(last))
+        };
+        let (cursor, anchor) = (position(cursor), position(anchor));
+        self.cursor = if self.follow {
+            last
+        } else {
+            cursor.unwrap_or(0)
+        };
+        self.selection_anchor = anchor;
+        self.changes = changes;
+        self.changed_at = Some(Instant::now());
+    }
+
+    /// Whether the byte at `offset` changed in the last reload, for a while
+    pub fn is_recently_changed(&self, offset: usize) -> bool {
+        self.changed_at
+            .is_some_and(|instant| instant.elapsed() < CHANGE_HIGHLIGHT)
+            && self.changes.iter().any(|range| range.contains(&offset))
+    }
+
+    /// Applies the result of a background thread working on the content of
+    /// generation `generation` of this buffer, which is dropped if the
+    /// content was replaced since. Returns whether it was applied.
+    pub fn handle_worker_msg(&mut self, generation: u64, msg: WorkerMsg) -> bool {
+        if generation != self.tx.generation() {
+            debug!("result computed from a former content dropped");
+            return false;
+        }
+        match msg {
+            WorkerMsg::MinimapBlocks { first, blocks } => self.minimap.update(first, blocks),
+            WorkerMsg::Strings {
+                encoding,
+                min_len,
+                strings,
+            } => self.strings.update(encoding, min_len, strings),
+            WorkerMsg::Statistics(stats) => self.stats.update(*stats),
+            WorkerMsg::FileLoading(percent) => self.fileinfo.loading = Some(percent),
+            WorkerMsg::FileLoaded(Ok(content)) => {
+                self.fileinfo.set_content(content);
+                self.start();
+                if self.follow {
+                    self.cursor = self.view_len().saturating_sub(1);
+                }
+            }
+            WorkerMsg::FileLoaded(Err(err)) => {
+                self.fileinfo.loading = None;
+                self.fileinfo.read_error = Some(err);
+            }
+            #[cfg(target_os = "linux")]
+            WorkerMsg::FileType(filetype) => {
+                self.fileinfo.filetype = match filetype {
+                    Ok(filetype) => Analysis::Done(filetype),
+                    Err(err) => Analysis::Failed(err),
+                };
+            }
+            #[cfg(not(target_os = "linux"))]
+            WorkerMsg::FileType(_) => {}
+            WorkerMsg::HashProgress { job, done, total } => {
+                if self.sha256_job == Some(job) {
+                    let percent = (done * 100).checked_div(total).unwrap_or(100);
+                    self.fileinfo.sha256 = Analysis::Running(percent);
+                }
+                self.hashes.progress(job, done, total);
+            }
+            WorkerMsg::HashDone { job, results } => {
+                let sha256 = results
+                    .iter()
+                    .find(|(algorithm, _)| *algorithm == HashAlgorithm::Sha256);
+                if self.sha256_job == Some(job)
+                    && let Some((_, sha256)) = sha256
+                {
+                    debug!("sha256 computed: {sha256}");
+                    if let Err(err) = self.bookmarks.load(sha256.clone()) {
+                        warn!("{err}");
+                    }
+                    self.fileinfo.sha256 = Analysis::Done(sha256.clone());
+                }
+                self.hashes.done(job, results);
+            }
+            WorkerMsg::FileChanged { content, changes } => self.reload(content, changes),
+        }
+        true
+    }
+}
+
+impl Drop for Buffer {
+    /// Stops the background threads, the watcher among them
+    fn drop(&mut self) {
+        self.tx.renew();
+    }
+}
@@ -1,4 +1,13 @@
-use clap::Parser;
+use clap::{ArgGroup, Parser};
+
+use crate::addressing::{self, AddressMap, Segment};
+use crate::display::{DisplayOptions, Endian, Radix, WordFormat};
+use crate::dump::{DumpFormat, DumpOptions};
+use crate::encoding::{CharTable, Encoding};
+use crate::export::{ExportFormat, ExportOptions};
+
+/// Maximum number of bytes per line of a dump
+const MAX_COLS: u16 = 4096;
 
 fn blocksize_in_range(s: &str) -> Result<u16, String> {
     let blocksize = s
@@ -13,13 +22,183 @@ fn blocksize_in_range(s: &str) -> Result<u16, String> {
     }
 }
 
+fn cols_in_range(s: &str) -> Result<u16, String> {
+    match s.parse() {
+        Ok(cols @ 1..=MAX_COLS) => Ok(cols),
+        _ => Err(format!(
+            "'{s}' is not a valid number of columns, expected 1 to {MAX_COLS}"
+        )),
+    }
+}
+
+fn group_size(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(group @ (1 | 2 | 4 | 8)) => Ok(group),
+        _ => Err(format!(
+            "'{s}' is not a valid word size, expected 1, 2, 4 or 8"
+        )),
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "hexplore")]
+#[command(group(ArgGroup::new("batch").args(["dump", "revert", "export"])))]
 #[command(version, about)]
 pub struct Args {
-    #[arg(help = "The file that should be opened")]
-    pub file: String,
+    #[arg(help = "The files that should be opened, each in its own buffer ('-' for stdin)")]
+    #[arg(value_name = "FILE")]
+    pub files: Vec<String>,
+    #[arg(long, help = "Compare two files side by side")]
+    #[arg(num_args = 2, value_names = ["FILE_A", "FILE_B"], conflicts_with = "files")]
+    pub diff: Option<Vec<String>>,
+    #[arg(long, help = "Browse the memory of a running process")]
+    #[arg(conflicts_with_all = ["files", "diff"])]
+    pub pid: Option<u32>,
+    #[arg(long, help = "Align the diff on inserted and deleted bytes")]
+    #[arg(requires = "diff")]
+    pub align: bool,
+    #[arg(
+        long,
+        help = "Let :save overwrite the opened file or device with the displayed bytes, \
+                re-encoded in the chosen format (bytes cannot be edited yet)"
+    )]
+    pub allow_write: bool,
+    #[arg(
+        short,
+        long,
+        help = "Stay at the end of the file when it grows, like `tail -f`"
+    )]
+    pub follow: bool,
+    #[arg(long, help = "Virtual address where the file is loaded")]
+    #[arg(value_name = "ADDRESS", value_parser = addressing::parse_address)]
+    pub base: Option<usize>,
+    #[arg(long, help = "Map file offsets to virtual addresses, can be repeated")]
+    #[arg(value_name = "OFFSET:LEN@ADDRESS", value_parser = addressing::parse_segment)]
+    #[arg(conflicts_with = "base")]
+    pub map: Vec<Segment>,
+    #[arg(long, help = "Radix of the data pane", default_value = "hex")]
+    pub radix: Radix,
+    #[arg(long, help = "Number of bytes displayed as a word in the data pane")]
+    #[arg(value_name = "BYTES", value_parser = group_size, default_value = "1")]
+    pub group: usize,
+    #[arg(long, help = "Byte order of the words", default_value = "little")]
+    pub endian: Endian,
+    #[arg(long, help = "Display words as numbers", default_value = "raw")]
+    pub word_format: WordFormat,
+    #[arg(long, help = "Radix of the address column", default_value = "hex")]
+    pub address_radix: Radix,
+    #[arg(
+        long,
+        help = "Decoding of the character pane [default: ascii, or custom with --table]"
+    )]
+    pub encoding: Option<Encoding>,
+    #[arg(
+        long,
+        help = "Custom decoding of the character pane, made of XX=c lines"
+    )]
+    #[arg(value_name = "FILE", value_parser = CharTable::load)]
+    #[arg(required_if_eq("encoding", "custom"))]
+    pub table: Option<CharTable>,
+    #[arg(long, help = "Display hexadecimal bytes in lowercase")]
+    pub lowercase: bool,
+    #[arg(long, help = "Display hexadecimal addresses in uppercase")]
+    pub uppercase_address: bool,
+    #[arg(
+        long,
+        help = "Write a hexdump to stdout instead of starting the interface"
+    )]
+    #[arg(value_name = "FORMAT", num_args = 0..=1, require_equals = true)]
+    #[arg(default_missing_value = "hexplore", conflicts_with_all = ["diff"])]
+    pub dump: Option<DumpFormat>,
+    #[arg(
+        long,
+        help = "Rebuild the binary of a dump and write it to stdout, like `xxd -r`"
+    )]
+    #[arg(value_name = "FORMAT", num_args = 0..=1, require_equals = true)]
+    #[arg(default_missing_value = "hexplore", conflicts_with_all = ["diff", "pid"])]
+    pub revert: Option<DumpFormat>,
+    #[arg(
+        long,
+        help = "Write the bytes as a literal to stdout instead of starting the interface"
+    )]
+    #[arg(value_name = "FORMAT", conflicts_with_all = ["diff"])]
+    pub export: Option<ExportFormat>,
+    #[arg(long, help = "Identifier of the exported variable")]
+    #[arg(value_name = "ID")]
+    pub name: Option<String>,
+    #[arg(
+        long,
+        help = "Bytes per line of exported arrays, or characters per line of strings (0 for a single line)"
+    )]
+    #[arg(value_name = "N")]
+    pub line_width: Option<usize>,
+    #[arg(
+        long,
+        help = "First byte of the dump or export, or address of the first reverted byte"
+    )]
+    #[arg(value_name = "OFFSET", value_parser = addressing::parse_address, requires = "batch")]
+    pub offset: Option<usize>,
+    #[arg(long, help = "Number of bytes of the dump or export")]
+    #[arg(value_name = "LENGTH", value_parser = addressing::parse_address, requires = "batch")]
+    pub length: Option<usize>,
+    #[arg(long, help = "Number of bytes per line of the dump")]
+    #[arg(value_parser = cols_in_range, requires = "dump")]
+    pub cols: Option<u16>,
     #[arg(short, long, help = "The number of bytes per block")]
     #[arg(value_parser = blocksize_in_range)]
     pub blocksize: Option<u16>,
 }
+
+impl Args {
+    pub fn display_options(&self) -> DisplayOptions {
+        DisplayOptions {
+            radix: self.radix,
+            group: match self.word_format {
+                // no float smaller than 4 bytes
+                WordFormat::Float => std::cmp::max(self.group, 4),
+                _ => self.group,
+            },
+            endian: self.endian,
+            word_format: self.word_format,
+            encoding: match (self.encoding, &self.table) {
+                (Some(encoding), _) => encoding,
+                (None, Some(_)) => Encoding::Custom,
+                (None, None) => Encoding::Ascii,
+            },
+            address_radix: self.address_radix,
+            uppercase: !self.lowercase,
+            uppercase_address: self.uppercase_address,
+        }
+    }
+
+    /// Range and formatting given with `--dump` or `--revert`
+    pub fn dump_options(&self) -> Option<DumpOptions> {
+        Some(DumpOptions {
+            format: self.dump.or(self.revert)?,
+            offset: self.offset.unwrap_or(0),
+            length: self.length,
+            cols: self.cols,
+            blocksize: self.blocksize,
+            display: self.display_options(),
+            char_table: self.table.clone(),
+        })
+    }
+
+    /// Layout given with `--name` and `--line-width`, for `--export` and the
+    /// `export` command
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions {
+            name: self.name.clone(),
+            width: self.line_width,
+        }
+    }
+
+    /// Virtual addresses given with `--base` or `--map`
+    pub fn address_map(&self) -> Option<AddressMap> {
+        match self.base {
+            Some(base) => Some(AddressMap::with_base(base)),
+            None if !self.map.is_empty() => Some(AddressMap::new(self.map.clone())),
+            None => None,
+        }
+    }
+}
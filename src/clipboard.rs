@@ -0,0 +1,11 @@
+use std::io::{self, Write};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Copies `text` to the system clipboard with the OSC 52 escape sequence,
+/// which is supported by most terminal emulators (even through SSH)
+pub fn copy_osc52(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -0,0 +1,364 @@
+#[cfg(unix)]
+use std::path::Path;
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use log::debug;
+use ratatui::style::Color;
+
+#[cfg(unix)]
+use crate::device;
+use crate::{
+    addressing::{self, AddressMap, Segment},
+    app::App,
+    clipboard,
+    compression::Compression,
+    export::{self, ExportFormat, ExportOptions},
+    firmware::{self, ImageFormat},
+};
+
+const EXPORT_USAGE: &str = "usage: export <format> [name=ID] [width=N] [file]";
+const REGION_USAGE: &str = "usage: region <label|\"label\"> [color=COLOR] [note]";
+
+/// Commands typed in the prompt opened with `SPACE`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Moves the cursor to an address of the displayed address space
+    Goto(usize),
+    /// Opens a file in a new buffer
+    Open(String),
+    NextBuffer,
+    PreviousBuffer,
+    /// Shows the buffer list popup
+    Buffers,
+    /// Bookmarks the cursor as `name`
+    Bookmark {
+        name: String,
+        comment: String,
+    },
+    /// Shows the bookmark list popup
+    Bookmarks,
+    /// Labels the selection as a region
+    Region {
+        label: String,
+        color: Option<String>,
+        note: String,
+    },
+    /// Describes the region at the cursor
+    ShowRegion,
+    /// Removes the region `label`, or the one at the cursor
+    Unregion(Option<String>),
+    /// Loads the whole file at a virtual address
+    Base(usize),
+    /// Adds a segment to the virtual addresses, or lists them
+    Map(Option<Segment>),
+    /// Removes the virtual addresses
+    Unmap,
+    /// Opens the stream at the cursor decompressed, its format being
+    /// recognized if `None`
+    Decompress(Option<Compression>),
+    /// Writes the displayed bytes to a file, in the format of the opened file
+    /// if `None`
+    Save {
+        format: Option<ImageFormat>,
+        path: String,
+    },
+    /// Writes the selection as a literal to a file, or to the clipboard
+    Export {
+        format: ExportFormat,
+        options: ExportOptions,
+        path: Option<String>,
+    },
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, arg) = match s.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (s, ""),
+        };
+
+        match (name, arg) {
+            // a bare address, like `:123` in vim
+            (address, "") if address.starts_with(|c: char| c.is_ascii_digit()) => {
+                addressing::parse_address(address).map(Command::Goto)
+            }
+            ("goto", "") => Err(String::from("usage: goto <address>")),
+            ("goto", address) => addressing::parse_address(address).map(Command::Goto),
+            ("open" | "e", "") => Err(format!("usage: {name} <file>")),
+            ("open" | "e", path) => Ok(Command::Open(path.to_string())),
+            ("bnext" | "bn", "") => Ok(Command::NextBuffer),
+            ("bprev" | "bp", "") => Ok(Command::PreviousBuffer),
+            ("buffers" | "ls", "") => Ok(Command::Buffers),
+            ("base", "") => Err(String::from("usage: base <address>")),
+            ("bookmark" | "bm", "") => Err(String::from("usage: bookmark <name> [comment]")),
+            ("bookmark" | "bm", args) => {
+                let (name, comment) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+                Ok(Command::Bookmark {
+                    name: name.to_string(),
+                    comment: comment.trim().to_string(),
+                })
+            }
+            ("bookmarks", "") => Ok(Command::Bookmarks),
+            ("region", "") => Ok(Command::ShowRegion),
+            ("region", args) => parse_region(args),
+            ("unregion", "") => Ok(Command::Unregion(None)),
+            ("unregion", label) => Ok(Command::Unregion(Some(label.trim_matches('"').to_string()))),
+            ("base", address) => addressing::parse_address(address).map(Command::Base),
+            ("map", "") => Ok(Command::Map(None)),
+            ("map", segment) => addressing::parse_segment(segment).map(|s| Command::Map(Some(s))),
+            ("unmap", "") => Ok(Command::Unmap),
+            ("decompress", "") => Ok(Command::Decompress(None)),
+            ("decompress", format) => Compression::from_str(format, true)
+                .map(|compression| Command::Decompress(Some(compression)))
+                .map_err(|_| format!("unknown compression '{format}'")),
+            ("save", "") => Err(String::from("usage: save [raw|ihex|srec|ti-txt] <file>")),
+            ("save", args) => Ok(match args.split_once(char::is_whitespace) {
+                Some((format, path)) if ImageFormat::from_str(format, true).is_ok() => {
+                    Command::Save {
+                        format: ImageFormat::from_str(format, true).ok(),
+                        path: path.trim_start().to_string(),
+                    }
+                }
+                _ => Command::Save {
+                    format: None,
+                    path: args.to_string(),
+                },
+            }),
+            ("export", "") => Err(String::from(EXPORT_USAGE)),
+            ("export", args) => parse_export(args),
+            ("quit" | "q", "") => Ok(Command::Quit),
+            _ => Err(format!("unknown command '{s}'")),
+        }
+    }
+}
+
+/// Parses `<format> [name=ID] [width=N] [file]`, the file being the rest of
+/// the line
+fn parse_export(args: &str) -> Result<Command, String> {
+    let (format, mut rest) = args
+        .split_once(char::is_whitespace)
+        .map_or((args, ""), |(format, rest)| (format, rest.trim_start()));
+    let format = ExportFormat::from_str(format, true).map_err(|_| {
+        let formats = ExportFormat::value_variants()
+            .iter()
+            .filter_map(|f| f.to_possible_value())
+            .map(|f| f.get_name().to_string())
+            .collect::<Vec<_>>();
+        format!("unknown format '{format}', expected {}", formats.join(", "))
+    })?;
+
+    let mut options = ExportOptions::default();
+    loop {
+        let (word, next) = rest
+            .split_once(char::is_whitespace)
+            .map_or((rest, ""), |(word, next)| (word, next.trim_start()));
+        if let Some(name) = word.strip_prefix("name=") {
+            options.name = Some(name.to_string());
+        } else if let Some(width) = word.strip_prefix("width=") {
+            let width = width
+                .parse()
+                .map_err(|_| format!("'{width}' is not a valid width"))?;
+            options.width = Some(width);
+        } else {
+            break;
+        }
+        rest = next;
+    }
+    let path = Some(rest.to_string()).filter(|path| !path.is_empty());
+
+    Ok(Command::Export {
+        format,
+        options,
+        path,
+    })
+}
+
+/// Parses `<label> [color=COLOR] [note]`, the label being quoted if it holds
+/// spaces and the note being the rest of the line
+fn parse_region(args: &str) -> Result<Command, String> {
+    let (label, rest) = match args.strip_prefix('"') {
+        Some(quoted) => quoted
+            .split_once('"')
+            .ok_or_else(|| String::from(REGION_USAGE))?,
+        None => args.split_once(char::is_whitespace).unwrap_or((args, "")),
+    };
+    if label.is_empty() {
+        return Err(String::from(REGION_USAGE));
+    }
+    let rest = rest.trim_start();
+    let (color, note) = match rest.strip_prefix("color=") {
+        Some(color) => {
+            let (color, note) = color.split_once(char::is_whitespace).unwrap_or((color, ""));
+            if Color::from_str(color).is_err() {
+                return Err(format!("unknown color '{color}'"));
+            }
+            (Some(color.to_string()), note.trim_start())
+        }
+        None => (None, rest),
+    };
+
+    Ok(Command::Region {
+        label: label.to_string(),
+        color,
+        note: note.to_string(),
+    })
+}
+
+impl App {
+    /// Runs the command typed in the prompt, reporting errors in the footer
+    pub fn execute_command(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+        let command = match input.parse::<Command>() {
+            Ok(command) => command,
+            Err(err) => {
+                self.message = Some(err);
+                return;
+            }
+        };
+        debug!("execute command {command:?}");
+
+        match command {
+            Command::Goto(address) => {
+                if let Err(err) = self.goto(address) {
+                    self.message = Some(err);
+                }
+            }
+            Command::Open(path) => {
+                if let Err(err) = self.open(&path) {
+                    self.message = Some(format!("failed to open '{path}': {err}"));
+                }
+            }
+            Command::NextBuffer => self.next_buffer(),
+            Command::PreviousBuffer => self.previous_buffer(),
+            Command::Buffers => {
+                self.buffers_selected = self.current;
+                self.show_buffers = true;
+            }
+            Command::Bookmark { name, comment } => self.set_bookmark(&name, &comment),
+            Command::Bookmarks => {
+                self.bookmarks_selected = 0;
+                self.show_bookmarks = true;
+            }
+            Command::Region { label, color, note } => {
+                self.add_region(&label, color.as_deref(), &note);
+            }
+            Command::ShowRegion => {
+                self.message = Some(match self.region_at_cursor() {
+                    Some(region) => {
+                        let range = region.range();
+                        let mut text =
+                            format!("{} {:#x}..{:#x}", region.label, range.start, range.end);
+                        if !region.note.is_empty() {
+                            text.push_str(&format!(": {}", region.note));
+                        }
+                        text
+                    }
+                    None => String::from("no region at the cursor"),
+                });
+            }
+            Command::Unregion(label) => self.remove_region(label.as_deref()),
+            Command::Base(base) => {
+                self.buffer.set_address_map(AddressMap::with_base(base));
+                self.scroll_to_cursor();
+            }
+            Command::Map(Some(segment)) => {
+                let mut segments = self.buffer.address_map.segments().to_vec();
+                segments.push(segment);
+                self.buffer.set_address_map(AddressMap::new(segments));
+                self.scroll_to_cursor();
+            }
+            Command::Map(None) => {
+                let segments = self.buffer.address_map.segments();
+                self.message = Some(if segments.is_empty() {
+                    String::from("no virtual address")
+                } else {
+                    let segments = segments.iter().map(Segment::to_string).collect::<Vec<_>>();
+                    format!("segments: {}", segments.join(", "))
+                });
+            }
+            Command::Unmap => {
+                self.buffer.set_address_map(AddressMap::default());
+                self.scroll_to_cursor();
+            }
+            Command::Decompress(compression) => {
+                if let Err(err) = self.decompress_at_cursor(compression) {
+                    self.message = Some(err);
+                }
+            }
+            Command::Save { format, path } => {
+                let format = format.unwrap_or(self.buffer.fileinfo.format);
+                let res = self.save(format, &path);
+                self.message = Some(match res {
+                    Ok(len) => format!("saved {len} bytes as {} to '{path}'", format.name()),
+                    Err(err) => format!("failed to save '{path}': {err}"),
+                });
+            }
+            Command::Export {
+                format,
+                options,
+                path,
+            } => {
+                // the layout given in the prompt overrides the cli flags
+                let options = ExportOptions {
+                    name: options.name.or_else(|| self.export.name.clone()),
+                    width: options.width.or(self.export.width),
+                };
+                let range = self.selection_or_file();
+                let len = range.len();
+                let text = export::export(&self.buffer.fileinfo.content[range], format, &options);
+                let name = format.name();
+                self.message = Some(match path {
+                    Some(path) => match std::fs::write(&path, text) {
+                        Ok(()) => format!("exported {len} bytes as {name} to '{path}'"),
+                        Err(err) => format!("failed to write '{path}': {err}"),
+                    },
+                    None => match clipboard::copy_osc52(&text) {
+                        Ok(()) => format!("copied {len} bytes as {name} to clipboard"),
+                        Err(err) => format!("failed to copy {name}: {err}"),
+                    },
+                });
+            }
+            Command::Quit => self.quit = true,
+        }
+    }
+
+    /// Writes the bytes of the buffer to `path` as `format`, returning the
+    /// number of bytes written
+    fn save(&self, format: ImageFormat, path: &str) -> Result<usize, String> {
+        let fileinfo = &self.buffer.fileinfo;
+        // the bytes are written at their virtual addresses, if any
+        let segments = match self.buffer.address_map.segments() {
+            [] => vec![Segment {
+                offset: 0,
+                len: fileinfo.size,
+                address: 0,
+            }],
+            segments => segments.to_vec(),
+        };
+        let bytes = firmware::write(&fileinfo.content, &segments, fileinfo.entry, format)?;
+
+        if fileinfo.is_at(path) && !fileinfo.writable {
+            return Err(String::from(
+                "it is the opened file, which is only overwritten with --allow-write",
+            ));
+        }
+        // a device is overwritten in place, only the opened one on purpose
+        #[cfg(unix)]
+        if device::device_kind(Path::new(path)).is_some() {
+            if !fileinfo.is_at(path) {
+                return Err(String::from("only the opened device is written to"));
+            }
+            device::write(Path::new(path), &bytes).map_err(|err| err.to_string())?;
+            return Ok(bytes.len());
+        }
+        std::fs::write(path, &bytes).map_err(|err| err.to_string())?;
+        Ok(bytes.len())
+    }
+}
@@ -0,0 +1,185 @@
+use std::fmt;
+use std::io::{self, Read};
+
+use clap::ValueEnum;
+use log::debug;
+
+use crate::{
+    app::App,
+    file::{FileInfo, Source},
+};
+
+/// Maximum size of a decompressed stream, against decompression bombs
+const MAX_DECOMPRESSED_LEN: u64 = 1 << 30;
+
+/// Compression formats which can be opened decompressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    /// Deflate with a 2 bytes header, as in PNG or git objects
+    Zlib,
+    /// Deflate without header, only with the `decompress` command
+    Deflate,
+    Xz,
+    /// Legacy `.lzma` files, often found in firmware
+    Lzma,
+    Zstd,
+    Bzip2,
+    /// LZ4 frame format
+    Lz4,
+}
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zlib => "zlib",
+            Compression::Deflate => "deflate",
+            Compression::Xz => "xz",
+            Compression::Lzma => "lzma",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    /// Compressed stream starting `data`, recognized by its magic bytes
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x1f, 0x8b, 0x08, ..] => Some(Compression::Gzip),
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(Compression::Xz),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Compression::Zstd),
+            [b'B', b'Z', b'h', b'1'..=b'9', ..] => Some(Compression::Bzip2),
+            [0x04, 0x22, 0x4d, 0x18, ..] => Some(Compression::Lz4),
+            // properties lc=3 lp=0 pb=2 of `lzma`, and a power of 2 dictionary
+            [0x5d, d0, d1, d2, d3, ..]
+                if u32::from_le_bytes([*d0, *d1, *d2, *d3]).is_power_of_two() =>
+            {
+                Some(Compression::Lzma)
+            }
+            // deflate method, at most a 32 KiB window, no dictionary
+            [cmf, flg, ..]
+                if cmf & 0x0f == 8
+                    && cmf >> 4 <= 7
+                    && flg & 0x20 == 0
+                    && (*cmf as u16 * 256 + *flg as u16).is_multiple_of(31) =>
+            {
+                Some(Compression::Zlib)
+            }
+            _ => None,
+        }
+    }
+
+    /// Decompresses the stream starting `data`, the bytes following it being
+    /// ignored
+    pub fn decompress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = vec![];
+        let limited = |reader: &mut dyn Read, output: &mut Vec<u8>| {
+            reader.take(MAX_DECOMPRESSED_LEN).read_to_end(output)
+        };
+        match self {
+            Compression::Gzip => limited(&mut flate2::read::GzDecoder::new(data), &mut output)?,
+            Compression::Zlib => limited(&mut flate2::read::ZlibDecoder::new(data), &mut output)?,
+            Compression::Deflate => {
+                limited(&mut flate2::read::DeflateDecoder::new(data), &mut output)?
+            }
+            Compression::Zstd => {
+                let mut decoder = ruzstd::decoding::StreamingDecoder::new(data)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+                limited(&mut decoder, &mut output)?
+            }
+            Compression::Bzip2 => limited(&mut bzip2_rs::DecoderReader::new(data), &mut output)?,
+            Compression::Lz4 => {
+                limited(&mut lz4_flex::frame::FrameDecoder::new(data), &mut output)?
+            }
+            Compression::Xz | Compression::Lzma => {
+                let mut input = io::BufReader::new(data);
+                let mut writer = LimitedWriter(&mut output);
+                let res = match self {
+                    Compression::Xz => lzma_rs::xz_decompress(&mut input, &mut writer),
+                    _ => lzma_rs::lzma_decompress(&mut input, &mut writer),
+                };
+                res.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+                output.len()
+            }
+        };
+        Ok(output)
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Output of the decoders writing their data, failing past
+/// `MAX_DECOMPRESSED_LEN` bytes
+struct LimitedWriter<'a>(&'a mut Vec<u8>);
+
+impl io::Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0.len() as u64 + buf.len() as u64 > MAX_DECOMPRESSED_LEN {
+            return Err(io::Error::other("the decompressed data is too big"));
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl App {
+    /// Opens in a new buffer the stream starting at the cursor, decompressed
+    /// with `compression` or the format recognized from its magic bytes. The
+    /// whole file is decompressed if there is no stream at the cursor.
+    pub fn decompress_at_cursor(&mut self, compression: Option<Compression>) -> Result<(), String> {
+        let fileinfo = &self.buffer.fileinfo;
+        let cursor = self
+            .cursor_offset()
+            .ok_or_else(|| String::from("the cursor is not on a byte of the file"))?;
+
+        let content = &fileinfo.content;
+        let (compression, offset) = match compression {
+            Some(compression) => (compression, cursor),
+            None => match Compression::detect(&content[cursor..]) {
+                Some(compression) => (compression, cursor),
+                None => match Compression::detect(content) {
+                    Some(compression) => (compression, 0),
+                    None => {
+                        return Err(format!(
+                            "no compressed stream at {cursor:#x}, try 'decompress <format>'"
+                        ));
+                    }
+                },
+            },
+        };
+        let data = compression
+            .decompress(&content[offset..])
+            .map_err(|err| format!("failed to decompress {compression} at {offset:#x}: {err}"))?;
+        debug!(
+            "{} bytes decompressed from the {compression} stream at {offset:#x}",
+            data.len()
+        );
+
+        let len = data.len();
+        let fileinfo = FileInfo {
+            path: fileinfo.path.clone(),
+            name: format!("{} [{compression}@{offset:#x}]", fileinfo.name),
+            size: len,
+            content: data.into(),
+            source: Source::Decompressed {
+                compression,
+                offset,
+            },
+            ..FileInfo::default()
+        };
+        self.open_fileinfo(fileinfo);
+        self.message = Some(format!(
+            "{len} bytes decompressed from the {compression} stream at {offset:#x}"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::Path,
+};
+
+use log::debug;
+
+/// Sector size assumed when the kernel does not report one
+const DEFAULT_SECTOR_SIZE: usize = 512;
+/// Number of sectors read at once
+const SECTORS_PER_READ: usize = 2048;
+/// Character devices have no size and may never end (e.g. `/dev/zero`), only
+/// their beginning is read
+pub const CHAR_DEVICE_MAX_LEN: usize = 16 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Block,
+    Char,
+}
+
+/// Returns the kind of device at `path`, `None` for anything else
+pub fn device_kind(path: &Path) -> Option<DeviceKind> {
+    let file_type = fs::metadata(path).ok()?.file_type();
+    if file_type.is_block_device() {
+        Some(DeviceKind::Block)
+    } else if file_type.is_char_device() {
+        Some(DeviceKind::Char)
+    } else {
+        None
+    }
+}
+
+/// Opens the device to read it, it is only ever written by [`write`]
+pub fn open(path: &Path) -> io::Result<File> {
+    File::open(path)
+}
+
+/// Writes `bytes` at the beginning of the device, in place, as a device can
+/// be neither truncated nor created
+pub fn write(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    OpenOptions::new().write(true).open(path)?.write_all(bytes)
+}
+
+/// Logical sector size of a block device, as reported in sysfs
+pub fn sector_size(path: &Path) -> usize {
+    let Ok(metadata) = fs::metadata(path) else {
+        return DEFAULT_SECTOR_SIZE;
+    };
+    let dev = metadata.rdev();
+    // same encoding as the `major()` and `minor()` macros of glibc
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let sysfs = format!("/sys/dev/block/{major}:{minor}/queue/logical_block_size");
+
+    fs::read_to_string(&sysfs)
+        .ok()
+        .and_then(|size| size.trim().parse().ok())
+        .filter(|&size: &usize| size.is_power_of_two())
+        .unwrap_or(DEFAULT_SECTOR_SIZE)
+}
+
+/// Reads a whole block device, by chunks of whole sectors. Its size is found
+/// by seeking to its end, as `metadata().len()` is 0 for devices.
+pub fn read_block_device(file: &mut File, sector_size: usize) -> io::Result<Vec<u8>> {
+    let size = file.seek(SeekFrom::End(0))? as usize;
+    file.seek(SeekFrom::Start(0))?;
+    debug!("block device of {size} bytes, {sector_size} bytes sectors");
+
+    let mut content = vec![0; size];
+    let mut done = 0;
+    while done < size {
+        let end = std::cmp::min(done + sector_size * SECTORS_PER_READ, size);
+        match file.read(&mut content[done..end])? {
+            // the device shrunk while being read
+            0 => break,
+            n => done += n,
+        }
+    }
+    content.truncate(done);
+
+    Ok(content)
+}
+
+/// Reads at most `CHAR_DEVICE_MAX_LEN` bytes of a character device, returning
+/// whether there was more to read
+pub fn read_char_device(file: &mut File) -> io::Result<(Vec<u8>, bool)> {
+    let mut content = vec![];
+    file.take(CHAR_DEVICE_MAX_LEN as u64 + 1)
+        .read_to_end(&mut content)?;
+    let truncated = content.len() > CHAR_DEVICE_MAX_LEN;
+    content.truncate(CHAR_DEVICE_MAX_LEN);
+
+    Ok((content, truncated))
+}
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ratatui::text::{Line, Text};
+
+use crate::file::FileInfo;
+
+/// Size of the blocks looked for in the compared file when aligning
+const BLOCK_SIZE: usize = 32;
+/// Multiplier of the rolling hash
+const HASH_BASE: u64 = 0x100000001b3;
+/// Maximum number of positions remembered for a block hash, so that highly
+/// repetitive data (e.g. padding) does not slow the alignment down
+const MAX_CANDIDATES: usize = 8;
+
+/// One of the two compared files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkKind {
+    Equal,
+    Replace,
+    /// Bytes only present in the right file
+    Insert,
+    /// Bytes only present in the left file
+    Delete,
+}
+
+/// Region of both files displayed side by side. `Equal` and `Replace` hunks
+/// have the same length on both sides, `Insert` and `Delete` hunks are empty
+/// on one side, which is rendered as a gap.
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub kind: HunkKind,
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+    /// Position of the hunk in the aligned view
+    pub start: usize,
+}
+
+impl Hunk {
+    fn len(&self) -> usize {
+        std::cmp::max(self.left.len(), self.right.len())
+    }
+
+    fn end(&self) -> usize {
+        self.start + self.len()
+    }
+}
+
+/// Comparison of the opened file (left) with another one (right).
+///
+/// Both files are displayed in an aligned view: a position in this view maps
+/// to a byte in each file, or to a gap when bytes were inserted or deleted.
+/// Without alignment, positions are simply file offsets.
+#[derive(Debug, Default)]
+pub struct Diff {
+    pub other: FileInfo,
+    pub aligned: bool,
+    hunks: Vec<Hunk>,
+    /// Sorted ranges of differing positions in the aligned view
+    pub ranges: Vec<Range<usize>>,
+    pub nb_bytes: usize,
+    pub inserted: usize,
+    pub deleted: usize,
+}
+
+impl Diff {
+    /// Compares `content` with `other` byte by byte. Bytes past the end of the
+    /// shortest file are considered different.
+    pub fn new(content: &[u8], other: FileInfo) -> Self {
+        let mut hunks = vec![];
+        let common = std::cmp::min(content.len(), other.size);
+        push_compared(&mut hunks, content, &other.content, 0..common, 0..common);
+        push_gaps(
+            &mut hunks,
+            common..content.len(),
+            common..other.content.len(),
+        );
+
+        Self::from_hunks(other, hunks, false)
+    }
+
+    /// Compares `content` with `other`, detecting inserted and deleted runs of
+    /// bytes so that shifted regions still line up.
+    ///
+    /// Blocks of the right file are indexed by a hash, then a rolling hash is
+    /// computed over the left file to find them (like rsync). Matches are
+    /// extended in both directions and kept in increasing order on both
+    /// sides, what lies between two matches is compared byte by byte.
+    pub fn new_aligned(content: &[u8], other: FileInfo) -> Self {
+        let left = content;
+        let right: &[u8] = &other.content;
+
+        let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+        for pos in (0..right.len().saturating_sub(BLOCK_SIZE - 1)).step_by(BLOCK_SIZE) {
+            let block = &right[pos..pos + BLOCK_SIZE];
+            // padding would match anywhere, it is aligned by extending real matches
+            if block.iter().all(|&b| b == block[0]) {
+                continue;
+            }
+            let candidates = index.entry(block_hash(block)).or_default();
+            if candidates.len() < MAX_CANDIDATES {
+                candidates.push(pos);
+            }
+        }
+
+        // highest power of the base, to remove the outgoing byte of the window
+        let base_pow = (1..BLOCK_SIZE).fold(1u64, |acc, _| acc.wrapping_mul(HASH_BASE));
+        let mut hunks = vec![];
+        // end of the last match, on both sides
+        let (mut left_done, mut right_done) = (0, 0);
+        let mut i = 0;
+        let mut hash = left.get(..BLOCK_SIZE).map(block_hash);
+
+        while let Some(h) = hash {
+            let found = index.get(&h).and_then(|candidates| {
+                // stay on the current diagonal when the block appears multiple times
+                let expected = right_done + (i - left_done);
+                candidates
+                    .iter()
+                    .filter(|&&pos| pos >= right_done)
+                    .filter(|&&pos| left[i..i + BLOCK_SIZE] == right[pos..pos + BLOCK_SIZE])
+                    .min_by_key(|&&pos| pos.abs_diff(expected))
+                    .copied()
+            });
+
+            if let Some(j) = found {
+                // extend the match backward then forward
+                let back = left[left_done..i]
+                    .iter()
+                    .rev()
+                    .zip(right[right_done..j].iter().rev())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let forward = left[i..]
+                    .iter()
+                    .zip(right[j..].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                let (start_left, start_right) = (i - back, j - back);
+
+                push_between(
+                    &mut hunks,
+                    left,
+                    right,
+                    left_done..start_left,
+                    right_done..start_right,
+                );
+                push_hunk(
+                    &mut hunks,
+                    HunkKind::Equal,
+                    start_left..i + forward,
+                    start_right..j + forward,
+                );
+                left_done = i + forward;
+                right_done = j + forward;
+                i = left_done;
+                hash = left.get(i..i + BLOCK_SIZE).map(block_hash);
+                continue;
+            }
+
+            // roll the window one byte forward
+            hash = left.get(i + BLOCK_SIZE).map(|&incoming| {
+                h.wrapping_sub((left[i] as u64).wrapping_mul(base_pow))
+                    .wrapping_mul(HASH_BASE)
+                    .wrapping_add(incoming as u64)
+            });
+            i += 1;
+        }
+
+        push_between(
+            &mut hunks,
+            left,
+            right,
+            left_done..left.len(),
+            right_done..right.len(),
+        );
+
+        Self::from_hunks(other, hunks, true)
+    }
+
+    fn from_hunks(other: FileInfo, hunks: Vec<Hunk>, aligned: bool) -> Self {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        let (mut inserted, mut deleted) = (0, 0);
+        for hunk in hunks.iter().filter(|hunk| hunk.kind != HunkKind::Equal) {
+            match hunk.kind {
+                HunkKind::Insert => inserted += hunk.len(),
+                HunkKind::Delete => deleted += hunk.len(),
+                _ => {}
+            }
+            match ranges.last_mut() {
+                Some(range) if range.end == hunk.start => range.end = hunk.end(),
+                _ => ranges.push(hunk.start..hunk.end()),
+            }
+        }
+
+        let nb_bytes = ranges.iter().map(|range| range.len()).sum();
+        Diff {
+            other,
+            aligned,
+            hunks,
+            ranges,
+            nb_bytes,
+            inserted,
+            deleted,
+        }
+    }
+
+    /// Length of the aligned view
+    pub fn view_len(&self) -> usize {
+        self.hunks.last().map(Hunk::end).unwrap_or_default()
+    }
+
+    fn hunk_at(&self, pos: usize) -> Option<&Hunk> {
+        let idx = self.hunks.partition_point(|hunk| hunk.end() <= pos);
+        self.hunks.get(idx)
+    }
+
+    /// Offset in the file of `side` displayed at `pos` in the aligned view,
+    /// `None` for a gap
+    pub fn offset(&self, side: Side, pos: usize) -> Option<usize> {
+        let hunk = self.hunk_at(pos)?;
+        let range = match side {
+            Side::Left => &hunk.left,
+            Side::Right => &hunk.right,
+        };
+        let k = pos - hunk.start;
+        (k < range.len()).then_some(range.start + k)
+    }
+
+    /// Position in the left file of `pos`, or of the next byte for a gap
+    fn left_position(&self, pos: usize) -> usize {
+        match self.hunk_at(pos) {
+            Some(hunk) => hunk.left.start + std::cmp::min(pos - hunk.start, hunk.left.len()),
+            None => self
+                .hunks
+                .last()
+                .map(|hunk| hunk.left.end)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Converts a range of the aligned view into a range of the left file
+    pub fn left_range(&self, range: Range<usize>) -> Range<usize> {
+        self.left_position(range.start)..self.left_position(range.end)
+    }
+
+    /// Converts an offset of the left file into a position in the aligned view
+    pub fn position_of(&self, offset: usize) -> usize {
+        let idx = self.hunks.partition_point(|hunk| hunk.left.end <= offset);
+        match self.hunks.get(idx) {
+            Some(hunk) => hunk.start + offset.saturating_sub(hunk.left.start),
+            None => self.view_len(),
+        }
+    }
+
+    pub fn is_different(&self, pos: usize) -> bool {
+        // index of the first range ending after pos
+        let idx = self.ranges.partition_point(|range| range.end <= pos);
+        self.ranges
+            .get(idx)
+            .is_some_and(|range| range.contains(&pos))
+    }
+
+    /// First differing range starting after `pos`
+    pub fn next_range(&self, pos: usize) -> Option<&Range<usize>> {
+        let idx = self.ranges.partition_point(|range| range.start <= pos);
+        self.ranges.get(idx)
+    }
+
+    /// Last differing range starting before `pos`
+    pub fn previous_range(&self, pos: usize) -> Option<&Range<usize>> {
+        let idx = self.ranges.partition_point(|range| range.start < pos);
+        idx.checked_sub(1).and_then(|idx| self.ranges.get(idx))
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} differing bytes in {} ranges",
+            self.nb_bytes,
+            self.ranges.len()
+        );
+        if self.aligned {
+            summary.push_str(&format!(
+                " ({} inserted, {} deleted)",
+                self.inserted, self.deleted
+            ));
+        }
+
+        summary
+    }
+
+    pub fn to_text(&self) -> Text<'_> {
+        let mut lines = vec![Line::from(self.summary()), Line::from("")];
+        for hunk in self
+            .hunks
+            .iter()
+            .filter(|hunk| hunk.kind != HunkKind::Equal)
+        {
+            let kind = match hunk.kind {
+                HunkKind::Replace => "changed",
+                HunkKind::Insert => "inserted",
+                HunkKind::Delete => "deleted",
+                HunkKind::Equal => continue,
+            };
+            lines.push(Line::from(format!(
+                "{:#010x}..{:#010x} | {:#010x}..{:#010x} {kind} ({} bytes)",
+                hunk.left.start,
+                hunk.left.end,
+                hunk.right.start,
+                hunk.right.end,
+                hunk.len(),
+            )));
+        }
+
+        Text::from(lines)
+    }
+}
+
+fn block_hash(block: &[u8]) -> u64 {
+    block.iter().fold(0u64, |hash, &b| {
+        hash.wrapping_mul(HASH_BASE).wrapping_add(b as u64)
+    })
+}
+
+/// Appends a hunk, merging it with the previous one when they are of the
+/// same kind
+fn push_hunk(hunks: &mut Vec<Hunk>, kind: HunkKind, left: Range<usize>, right: Range<usize>) {
+    if left.is_empty() && right.is_empty() {
+        return;
+    }
+    if let Some(last) = hunks.last_mut()
+        && last.kind == kind
+        && last.left.end == left.start
+        && last.right.end == right.start
+    {
+        last.left.end = left.end;
+        last.right.end = right.end;
+        return;
+    }
+
+    let start = hunks.last().map(Hunk::end).unwrap_or_default();
+    hunks.push(Hunk {
+        kind,
+        left,
+        right,
+        start,
+    });
+}
+
+/// Compares two ranges of the same length byte by byte
+fn push_compared(
+    hunks: &mut Vec<Hunk>,
+    left: &[u8],
+    right: &[u8],
+    left_range: Range<usize>,
+    right_range: Range<usize>,
+) {
+    let pairs = left[left_range.clone()]
+        .iter()
+        .zip(right[right_range.clone()].iter());
+    for (k, (a, b)) in pairs.enumerate() {
+        let kind = if a == b {
+            HunkKind::Equal
+        } else {
+            HunkKind::Replace
+        };
+        let (l, r) = (left_range.start + k, right_range.start + k);
+        push_hunk(hunks, kind, l..l + 1, r..r + 1);
+    }
+}
+
+/// Remaining bytes of one side only
+fn push_gaps(hunks: &mut Vec<Hunk>, left: Range<usize>, right: Range<usize>) {
+    push_hunk(
+        hunks,
+        HunkKind::Delete,
+        left.clone(),
+        right.start..right.start,
+    );
+    push_hunk(hunks, HunkKind::Insert, left.end..left.end, right);
+}
+
+/// Region between two matches: the common length is compared byte by byte,
+/// the rest is inserted or deleted
+fn push_between(
+    hunks: &mut Vec<Hunk>,
+    left: &[u8],
+    right: &[u8],
+    left_range: Range<usize>,
+    right_range: Range<usize>,
+) {
+    let common = std::cmp::min(left_range.len(), right_range.len());
+    let (left_end, right_end) = (left_range.start + common, right_range.start + common);
+    push_compared(
+        hunks,
+        left,
+        right,
+        left_range.start..left_end,
+        right_range.start..right_end,
+    );
+    push_gaps(hunks, left_end..left_range.end, right_end..right_range.end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn fileinfo(content: &[u8]) -> FileInfo {
+        FileInfo {
+            content: Arc::from(content),
+            size: content.len(),
+            ..FileInfo::default()
+        }
+    }
+
+    /// Bytes without repeated blocks
+    fn random(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    /// Aligns `left` with `right`, checking that the hunks cover both files
+    /// in order and that equal bytes are equal
+    fn aligned(left: &[u8], right: &[u8]) -> Diff {
+        let diff = Diff::new_aligned(left, fileinfo(right));
+        let (mut l, mut r, mut pos) = (0, 0, 0);
+        for hunk in &diff.hunks {
+            assert_eq!((hunk.left.start, hunk.right.start, hunk.start), (l, r, pos));
+            match hunk.kind {
+                HunkKind::Equal => assert_eq!(left[hunk.left.clone()], right[hunk.right.clone()]),
+                HunkKind::Replace => assert_eq!(hunk.left.len(), hunk.right.len()),
+                HunkKind::Insert => assert!(hunk.left.is_empty()),
+                HunkKind::Delete => assert!(hunk.right.is_empty()),
+            }
+            (l, r, pos) = (hunk.left.end, hunk.right.end, hunk.end());
+        }
+        assert_eq!((l, r), (left.len(), right.len()));
+        diff
+    }
+
+    fn kinds(diff: &Diff) -> Vec<(HunkKind, Range<usize>, Range<usize>)> {
+        diff.hunks
+            .iter()
+            .map(|hunk| (hunk.kind, hunk.left.clone(), hunk.right.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn empty_and_identical() {
+        let diff = aligned(&[], &[]);
+        assert_eq!(diff.view_len(), 0);
+        assert!(diff.ranges.is_empty());
+        assert_eq!(diff.position_of(0), 0);
+        assert_eq!(diff.left_range(0..0), 0..0);
+
+        let content = random(1000, 1);
+        let diff = aligned(&content, &content);
+        assert_eq!(kinds(&diff), [(HunkKind::Equal, 0..1000, 0..1000)]);
+        assert_eq!(diff.nb_bytes, 0);
+        assert_eq!(diff.position_of(500), 500);
+        assert_eq!(diff.next_range(0), None);
+
+        let diff = aligned(&[], &content);
+        assert_eq!(kinds(&diff), [(HunkKind::Insert, 0..0, 0..1000)]);
+        assert_eq!(diff.inserted, 1000);
+        let diff = aligned(&content, &[]);
+        assert_eq!(kinds(&diff), [(HunkKind::Delete, 0..1000, 0..0)]);
+        assert_eq!(diff.deleted, 1000);
+    }
+
+    #[test]
+    fn insertion() {
+        let left = random(4000, 1);
+        let right = [&left[..1000], &random(10, 2), &left[1000..]].concat();
+        let diff = aligned(&left, &right);
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Equal, 0..1000, 0..1000),
+                (HunkKind::Insert, 1000..1000, 1000..1010),
+                (HunkKind::Equal, 1000..4000, 1010..4010),
+            ]
+        );
+        assert_eq!((diff.inserted, diff.deleted), (10, 0));
+        assert_eq!(diff.ranges, std::slice::from_ref(&(1000..1010)));
+        assert_eq!(diff.view_len(), 4010);
+
+        assert_eq!(diff.offset(Side::Left, 1005), None);
+        assert_eq!(diff.offset(Side::Right, 1005), Some(1005));
+        assert_eq!(diff.offset(Side::Left, 1010), Some(1000));
+        assert_eq!(diff.position_of(999), 999);
+        assert_eq!(diff.position_of(1000), 1010);
+        assert_eq!(diff.position_of(4000), 4010);
+        // the gap holds no byte of the left file
+        assert_eq!(diff.left_range(1000..1010), 1000..1000);
+        assert_eq!(diff.left_range(990..1020), 990..1010);
+        assert_eq!(diff.left_range(0..diff.view_len()), 0..4000);
+    }
+
+    #[test]
+    fn deletion() {
+        let left = random(4000, 1);
+        let right = [&left[..1000], &left[1010..]].concat();
+        let diff = aligned(&left, &right);
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Equal, 0..1000, 0..1000),
+                (HunkKind::Delete, 1000..1010, 1000..1000),
+                (HunkKind::Equal, 1010..4000, 1000..3990),
+            ]
+        );
+        assert_eq!((diff.inserted, diff.deleted), (0, 10));
+        assert_eq!(diff.offset(Side::Right, 1005), None);
+        assert_eq!(diff.position_of(1005), 1005);
+        assert_eq!(diff.left_range(1000..1010), 1000..1010);
+    }
+
+    #[test]
+    fn replaced_bytes_stay_aligned() {
+        let left = random(4000, 1);
+        let mut right = left.clone();
+        for byte in &mut right[2000..2005] {
+            *byte = !*byte;
+        }
+        let diff = aligned(&left, &right);
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Equal, 0..2000, 0..2000),
+                (HunkKind::Replace, 2000..2005, 2000..2005),
+                (HunkKind::Equal, 2005..4000, 2005..4000),
+            ]
+        );
+        assert_eq!((diff.nb_bytes, diff.inserted, diff.deleted), (5, 0, 0));
+    }
+
+    #[test]
+    fn shifted_block() {
+        // B moved before A: the first match wins, so B is seen as inserted
+        // before A then deleted after it, C staying aligned
+        let (a, b, c) = (random(1000, 1), random(1000, 2), random(1000, 3));
+        let left = [&a[..], &b, &c].concat();
+        let right = [&b[..], &a, &c].concat();
+        let diff = aligned(&left, &right);
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Insert, 0..0, 0..1000),
+                (HunkKind::Equal, 0..1000, 1000..2000),
+                (HunkKind::Delete, 1000..2000, 2000..2000),
+                (HunkKind::Equal, 2000..3000, 2000..3000),
+            ]
+        );
+        assert_eq!((diff.inserted, diff.deleted), (1000, 1000));
+        assert_eq!(diff.ranges, [0..1000, 2000..3000]);
+        assert_eq!(diff.position_of(2500), 3500);
+    }
+
+    #[test]
+    fn repeated_patterns() {
+        let pattern = random(BLOCK_SIZE, 2);
+        let repeated = |n: usize| pattern.repeat(n);
+        let (head, tail) = (random(100, 1), random(100, 3));
+        let left = [&head[..], &repeated(10), &tail].concat();
+        let right = [&head[..], &repeated(11), &tail].concat();
+        let diff = aligned(&left, &right);
+        assert_eq!((diff.inserted, diff.deleted), (BLOCK_SIZE, 0));
+        assert_eq!(diff.nb_bytes, BLOCK_SIZE);
+        assert_eq!(diff.ranges.len(), 1);
+        assert_eq!(diff.view_len(), right.len());
+
+        // padding is aligned by extending the matches around it
+        let left = [&head[..], &[0; 200], &tail].concat();
+        let right = [&head[..], &[0; 264], &tail].concat();
+        let diff = aligned(&left, &right);
+        assert_eq!((diff.inserted, diff.deleted), (64, 0));
+        assert_eq!(diff.position_of(left.len() - 1), right.len() - 1);
+    }
+
+    #[test]
+    fn byte_by_byte_hunks_are_merged() {
+        let diff = Diff::new(b"abcdef", fileinfo(b"abXYef"));
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Equal, 0..2, 0..2),
+                (HunkKind::Replace, 2..4, 2..4),
+                (HunkKind::Equal, 4..6, 4..6),
+            ]
+        );
+        assert_eq!(diff.nb_bytes, 2);
+        assert_eq!(diff.ranges, std::slice::from_ref(&(2..4)));
+
+        // a change followed by extra bytes makes one range
+        let diff = Diff::new(b"abc", fileinfo(b"abXde"));
+        assert_eq!(
+            kinds(&diff),
+            [
+                (HunkKind::Equal, 0..2, 0..2),
+                (HunkKind::Replace, 2..3, 2..3),
+                (HunkKind::Insert, 3..3, 3..5),
+            ]
+        );
+        assert_eq!(diff.ranges, std::slice::from_ref(&(2..5)));
+        assert_eq!(diff.summary(), "3 differing bytes in 1 ranges");
+    }
+
+    #[test]
+    fn next_and_previous_ranges() {
+        let diff = Diff::new(b"aXcdeYYh", fileinfo(b"abcdefgh"));
+        assert_eq!(diff.ranges, [1..2, 5..7]);
+        assert_eq!(diff.next_range(0), Some(&(1..2)));
+        assert_eq!(diff.next_range(1), Some(&(5..7)));
+        assert_eq!(diff.next_range(5), None);
+        assert_eq!(diff.previous_range(7), Some(&(5..7)));
+        assert_eq!(diff.previous_range(5), Some(&(1..2)));
+        assert_eq!(diff.previous_range(1), None);
+        assert!(diff.is_different(6) && !diff.is_different(7));
+    }
+}
@@ -1,6 +1,274 @@
-use ratatui::text::Line;
+use std::fmt;
 
-use crate::app::App;
+use clap::ValueEnum;
+use ratatui::{
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    app::App,
+    diff::Side,
+    encoding::{Encoding, Glyph},
+    regions::Region,
+};
+
+/// Repeated to fill the cell of a gap in the data pane
+pub const GAP_DATA: char = '-';
+/// Repeated to fill the cell of an unmapped virtual address
+const UNMAPPED_DATA: char = '?';
+const GAP_ASCII: char = ' ';
+/// Displayed on the bytes of a multi-byte character following the first one
+const CONTINUATION: char = '·';
+/// Width of floats in the data pane, e.g. `-1.234567e-38`
+const FLOAT32_WIDTH: usize = 13;
+const FLOAT64_WIDTH: usize = 17;
+
+/// Base in which bytes and addresses are displayed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Radix {
+    #[default]
+    #[value(name = "hex")]
+    Hexadecimal,
+    #[value(name = "dec")]
+    Decimal,
+    #[value(name = "oct")]
+    Octal,
+    #[value(name = "bin")]
+    Binary,
+}
+
+impl Radix {
+    pub fn next(self) -> Self {
+        match self {
+            Radix::Hexadecimal => Radix::Decimal,
+            Radix::Decimal => Radix::Octal,
+            Radix::Octal => Radix::Binary,
+            Radix::Binary => Radix::Hexadecimal,
+        }
+    }
+
+    /// Title of the data pane
+    pub fn title(self) -> &'static str {
+        match self {
+            Radix::Hexadecimal => "Hex",
+            Radix::Decimal => "Dec",
+            Radix::Octal => "Oct",
+            Radix::Binary => "Bin",
+        }
+    }
+
+    pub fn base(self) -> usize {
+        match self {
+            Radix::Hexadecimal => 16,
+            Radix::Decimal => 10,
+            Radix::Octal => 8,
+            Radix::Binary => 2,
+        }
+    }
+
+    /// Width of a byte in the data pane
+    pub fn cell_width(self) -> usize {
+        count_digits(u8::MAX as usize, self)
+    }
+
+    /// Formats a byte on `cell_width` characters
+    pub fn format_byte(self, b: u8, uppercase: bool) -> String {
+        match (self, uppercase) {
+            (Radix::Hexadecimal, true) => format!("{b:02X}"),
+            (Radix::Hexadecimal, false) => format!("{b:02x}"),
+            // like `od -t u1`
+            (Radix::Decimal, _) => format!("{b:3}"),
+            (Radix::Octal, _) => format!("{b:03o}"),
+            (Radix::Binary, _) => format!("{b:08b}"),
+        }
+    }
+
+    /// Formats an address, zero-padded to `width` digits
+    pub fn format_address(self, address: usize, width: usize, uppercase: bool) -> String {
+        match (self, uppercase) {
+            (Radix::Hexadecimal, true) => format!("{address:0width$X}"),
+            (Radix::Hexadecimal, false) => format!("{address:0width$x}"),
+            (Radix::Decimal, _) => format!("{address:0width$}"),
+            (Radix::Octal, _) => format!("{address:0width$o}"),
+            (Radix::Binary, _) => format!("{address:0width$b}"),
+        }
+    }
+
+    /// Formats an address for the footer, with its prefix (e.g. `0x`)
+    pub fn format_prefixed(self, address: usize) -> String {
+        match self {
+            Radix::Hexadecimal => format!("{address:#x}"),
+            Radix::Decimal => format!("{address}"),
+            Radix::Octal => format!("{address:#o}"),
+            Radix::Binary => format!("{address:#b}"),
+        }
+    }
+}
+
+impl fmt::Display for Radix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Radix::Hexadecimal => "hex",
+            Radix::Decimal => "dec",
+            Radix::Octal => "oct",
+            Radix::Binary => "bin",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Byte order of the words of the data pane
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+/// How the words of the data pane are displayed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WordFormat {
+    /// Digits of the bytes in the data radix, like `xxd -e`
+    #[default]
+    Raw,
+    Unsigned,
+    Signed,
+    /// IEEE 754 floats, words of 4 or 8 bytes only
+    Float,
+}
+
+impl WordFormat {
+    pub fn next(self) -> Self {
+        match self {
+            WordFormat::Raw => WordFormat::Unsigned,
+            WordFormat::Unsigned => WordFormat::Signed,
+            WordFormat::Signed => WordFormat::Float,
+            WordFormat::Float => WordFormat::Raw,
+        }
+    }
+}
+
+/// How bytes and addresses are formatted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayOptions {
+    /// Radix of the data pane
+    pub radix: Radix,
+    /// Number of bytes displayed as a single word in the data pane (1, 2, 4
+    /// or 8)
+    pub group: usize,
+    pub endian: Endian,
+    pub word_format: WordFormat,
+    /// Decoding of the character pane
+    pub encoding: Encoding,
+    /// Radix of the address column, hexadecimal or decimal
+    pub address_radix: Radix,
+    /// Case of hexadecimal digits in the data pane
+    pub uppercase: bool,
+    /// Case of hexadecimal digits in the address column
+    pub uppercase_address: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        DisplayOptions {
+            radix: Radix::Hexadecimal,
+            group: 1,
+            endian: Endian::Little,
+            word_format: WordFormat::Raw,
+            encoding: Encoding::Ascii,
+            address_radix: Radix::Hexadecimal,
+            uppercase: true,
+            uppercase_address: false,
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Switches to the next word size, floats being at least 4 bytes
+    pub fn next_group(&mut self) {
+        self.group = match self.group {
+            1 if self.word_format != WordFormat::Float => 2,
+            1 | 2 => 4,
+            4 => 8,
+            _ if self.word_format == WordFormat::Float => 4,
+            _ => 1,
+        };
+    }
+
+    pub fn next_word_format(&mut self) {
+        self.word_format = self.word_format.next();
+        if self.word_format == WordFormat::Float {
+            self.group = std::cmp::max(self.group, 4);
+        }
+    }
+
+    /// Width of a word in the data pane
+    pub fn cell_width(&self) -> usize {
+        let bits = 8 * self.group as u32;
+        let max = u64::MAX >> (64 - bits);
+        match self.word_format {
+            WordFormat::Raw => match self.radix {
+                Radix::Hexadecimal | Radix::Binary => self.group * self.radix.cell_width(),
+                Radix::Decimal | Radix::Octal => count_digits_u64(max, self.radix),
+            },
+            WordFormat::Unsigned => count_digits_u64(max, Radix::Decimal),
+            // sign, and the minimum having one more than the maximum
+            WordFormat::Signed => count_digits_u64(max / 2 + 1, Radix::Decimal) + 1,
+            WordFormat::Float if self.group == 8 => FLOAT64_WIDTH,
+            WordFormat::Float => FLOAT32_WIDTH,
+        }
+    }
+
+    /// Title of the data pane
+    pub fn title(&self) -> String {
+        let endian = match self.endian {
+            Endian::Little => "LE",
+            Endian::Big => "BE",
+        };
+        let bits = 8 * self.group;
+        match self.word_format {
+            WordFormat::Raw if self.group == 1 => String::from(self.radix.title()),
+            WordFormat::Raw => format!("{} x{} {endian}", self.radix.title(), self.group),
+            // no byte order for single bytes
+            WordFormat::Unsigned if self.group == 1 => format!("u{bits}"),
+            WordFormat::Signed if self.group == 1 => format!("i{bits}"),
+            WordFormat::Unsigned => format!("u{bits} {endian}"),
+            WordFormat::Signed => format!("i{bits} {endian}"),
+            WordFormat::Float => format!("f{bits} {endian}"),
+        }
+    }
+
+    /// Value of a whole word, its bytes being in the file order
+    fn word_value(&self, bytes: &[u8]) -> u64 {
+        let fold = |value: u64, &b: &u8| (value << 8) | b as u64;
+        match self.endian {
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+            Endian::Big => bytes.iter().fold(0, fold),
+        }
+    }
+
+    /// Formats a whole word as a number, on `cell_width` characters
+    fn format_word(&self, bytes: &[u8]) -> String {
+        let width = self.cell_width();
+        let value = self.word_value(bytes);
+        let bits = 8 * bytes.len() as u32;
+        match (self.word_format, self.radix) {
+            (WordFormat::Raw, Radix::Octal) => format!("{value:0width$o}"),
+            (WordFormat::Signed, _) => {
+                // sign extension
+                let value = ((value << (64 - bits)) as i64) >> (64 - bits);
+                format!("{value:>width$}")
+            }
+            (WordFormat::Float, _) if bytes.len() == 8 => {
+                format!("{:>width$.9e}", f64::from_bits(value))
+            }
+            (WordFormat::Float, _) => format!("{:>width$.6e}", f32::from_bits(value as u32)),
+            _ => format!("{value:>width$}"),
+        }
+    }
+}
 
 impl App {
     pub fn get_address_to_lines(
@@ -8,81 +276,347 @@ impl App {
         start_line_idx: usize,
         end_line_idx: usize,
     ) -> Vec<Line<'static>> {
-        let cap = count_hexdigits(self.fileinfo.size);
+        let radix = self.display.address_radix;
+        let cap = count_digits(self.last_address(), radix);
+        let uppercase = self.display.uppercase_address;
         let mut text = vec![];
+        // lines holding a bookmark are marked in the padding of the column
+        let markers = self
+            .buffer
+            .bookmarks
+            .list
+            .iter()
+            .filter_map(|bookmark| {
+                let line = self.position_of_offset(bookmark.offset)? / self.bytes_per_row;
+                Some((line, bookmark.marker()))
+            })
+            .collect::<Vec<_>>();
 
         for idx in start_line_idx..end_line_idx {
-            let addr = idx * self.bytes_per_row;
-            let hex = format!("{addr:x}");
-            text.push(Line::from("0".repeat(cap - hex.len()) + &hex));
+            let pos = idx * self.bytes_per_row;
+            let address = match self.line_address(pos) {
+                Some(addr) => Span::from(radix.format_address(addr, cap, uppercase)),
+                // unmapped offsets are shown as is, dimmed
+                None => {
+                    let offset = self.view_offset(pos);
+                    Span::from(radix.format_address(offset, cap, uppercase)).fg(Color::DarkGray)
+                }
+            };
+            let line = match markers.iter().find(|(line, _)| *line == idx) {
+                Some((_, marker)) => Line::from(vec![
+                    Span::from(format!("{marker} ")).fg(Color::Yellow).bold(),
+                    address,
+                ]),
+                None => Line::from(address),
+            };
+            text.push(line);
         }
 
         text
     }
 
-    pub fn get_hexdump(&self, start_line_idx: usize, end_line_idx: usize) -> Vec<Line<'static>> {
-        let mut text = vec![];
-        let chunks_iter = self
-            .fileinfo
-            .content
-            .chunks(self.bytes_per_row)
-            .skip(start_line_idx)
-            .take(end_line_idx - start_line_idx);
+    /// Offset in the opened file of the position `pos` of the view
+    fn view_offset(&self, pos: usize) -> usize {
+        match self.buffer.diff.as_ref().filter(|diff| diff.aligned) {
+            // the aligned view shows offsets of the opened file
+            Some(diff) => diff.left_range(pos..pos).start,
+            None => self.buffer.offset_at(pos).unwrap_or(pos),
+        }
+    }
 
-        for chunk in chunks_iter {
-            text.push(Line::from(line_format_hex(chunk, self.blocksize)));
+    /// Address of the position `pos` of the view in the active address
+    /// space, `None` for an offset which is not mapped
+    fn line_address(&self, pos: usize) -> Option<usize> {
+        match self.buffer.span() {
+            Some(span) => Some(span.start + pos),
+            None => self.buffer.address_of(self.view_offset(pos)),
         }
-        text
     }
 
-    pub fn get_asciidump(&self, start_line_idx: usize, end_line_idx: usize) -> Vec<Line<'static>> {
+    /// Address displayed for the position `pos` of the view, in the active
+    /// address space (the offset itself if it is not mapped)
+    pub fn address_of(&self, pos: usize) -> usize {
+        self.line_address(pos)
+            .unwrap_or_else(|| self.view_offset(pos))
+    }
+
+    /// Highest address displayed, sizing the address column
+    pub fn last_address(&self) -> usize {
+        std::cmp::max(self.buffer.last_address(), self.data_size())
+    }
+
+    pub fn get_hexdump(
+        &self,
+        side: Side,
+        start_line_idx: usize,
+        end_line_idx: usize,
+    ) -> Vec<Line<'static>> {
         let mut text = vec![];
-        let chunks_iter = self
-            .fileinfo
-            .content
-            .chunks(self.bytes_per_row)
-            .skip(start_line_idx)
-            .take(end_line_idx - start_line_idx);
+        for idx in start_line_idx..end_line_idx {
+            let offset = idx * self.bytes_per_row;
+            let cells = self.line_cells(side, idx);
+            // no gap of the diff in the virtual addresses
+            let gap = match self.buffer.span() {
+                Some(_) => UNMAPPED_DATA,
+                None => GAP_DATA,
+            };
+            text.push(line_format_data(
+                &cells,
+                self.blocksize,
+                self.display,
+                gap,
+                |i| self.byte_style(side, offset + i),
+            ));
+        }
+        text
+    }
 
-        for chunk in chunks_iter {
-            text.push(Line::from(line_format_ascii(chunk)));
+    pub fn get_asciidump(
+        &self,
+        side: Side,
+        start_line_idx: usize,
+        end_line_idx: usize,
+    ) -> Vec<Line<'static>> {
+        let mut text = vec![];
+        for idx in start_line_idx..end_line_idx {
+            let offset = idx * self.bytes_per_row;
+            let glyphs = self.line_glyphs(side, idx);
+            text.push(line_format_chars(&glyphs, |i| {
+                self.byte_style(side, offset + i)
+            }));
         }
         text
     }
-}
 
-fn line_format_hex(bytes: &[u8], blocksize: u16) -> String {
-    let mut s = String::with_capacity(3 * bytes.len());
+    /// Decoded bytes of line `line_idx`, along with the bytes of the
+    /// surrounding lines for the characters spanning several lines
+    fn line_glyphs(&self, side: Side, line_idx: usize) -> Vec<Glyph> {
+        let cells = self.line_cells(side, line_idx);
+        let encoding = self.display.encoding;
+        if !matches!(
+            encoding,
+            Encoding::Utf8 | Encoding::Utf16Le | Encoding::Utf16Be
+        ) {
+            return encoding.decode(&cells, 0, self.char_table.as_ref());
+        }
+
+        let previous = match line_idx.checked_sub(1) {
+            Some(idx) => self.line_cells(side, idx),
+            None => vec![],
+        };
+        let next = self.line_cells(side, line_idx + 1);
+        encoding.decode_line(
+            &previous,
+            &cells,
+            &next,
+            line_idx * self.bytes_per_row,
+            self.char_table.as_ref(),
+        )
+    }
 
-    for (i, b) in bytes.iter().enumerate() {
-        if i != 0 && i % blocksize as usize == 0 {
-            s.push(' ');
+    /// Innermost region holding the byte at `pos` of the `side` pane, for the
+    /// opened file only
+    fn region_at(&self, side: Side, pos: usize) -> Option<&Region> {
+        if side != Side::Left || self.buffer.regions.list.is_empty() {
+            return None;
         }
-        s.push_str(format!("{b:02X} ").as_str());
+        let offset = match &self.buffer.diff {
+            Some(diff) => diff.offset(side, pos)?,
+            None => self.buffer.offset_at(pos)?,
+        };
+        self.buffer.regions.at(offset)
     }
-    // Pop last space
-    s.pop();
 
-    s
-}
+    /// Bytes displayed on line `line_idx` of the `side` pane, `None` being a
+    /// gap in the aligned diff view, an unreadable page of a process or an
+    /// unmapped virtual address
+    fn line_cells(&self, side: Side, line_idx: usize) -> Vec<Option<u8>> {
+        let start = line_idx * self.bytes_per_row;
+        let end = std::cmp::min(start + self.bytes_per_row, self.data_size());
 
-fn line_format_ascii(bytes: &[u8]) -> String {
-    let mut s = String::with_capacity(bytes.len());
+        match &self.buffer.diff {
+            Some(diff) => {
+                let content = match side {
+                    Side::Left => &self.buffer.fileinfo.content,
+                    Side::Right => &diff.other.content,
+                };
+                (start..end)
+                    .map(|pos| diff.offset(side, pos).map(|offset| content[offset]))
+                    .collect()
+            }
+            None => {
+                let fileinfo = &self.buffer.fileinfo;
+                (start..end)
+                    .map(|pos| {
+                        let offset = self.buffer.offset_at(pos)?;
+                        fileinfo
+                            .is_readable(offset)
+                            .then(|| fileinfo.content[offset])
+                    })
+                    .collect()
+            }
+        }
+    }
 
-    for b in bytes.iter() {
-        if b.is_ascii_graphic() {
-            s.push((*b).into());
+    /// Style of the byte at `pos` of the `side` pane, highlighting the cursor,
+    /// the selection, the differences with the compared file and the regions
+    /// of the opened file
+    fn byte_style(&self, side: Side, pos: usize) -> Style {
+        let style = if self
+            .buffer
+            .diff
+            .as_ref()
+            .is_some_and(|diff| diff.is_different(pos))
+        {
+            Style::default().fg(Color::Red).bold()
+        } else if self
+            .buffer
+            .offset_at(pos)
+            .is_some_and(|offset| self.buffer.is_recently_changed(offset))
+        {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else if let Some(color) = self.region_at(side, pos).and_then(Region::color) {
+            Style::default().bg(color)
         } else {
-            s.push('.');
+            Style::default()
+        };
+
+        if pos == self.buffer.cursor {
+            style.reversed()
+        } else if self
+            .selection()
+            .is_some_and(|selection| selection.contains(&pos))
+        {
+            style.bg(Color::DarkGray)
+        } else {
+            style
+        }
+    }
+}
+
+/// Formats the bytes of a line of the data pane, `None` being a gap filled
+/// with `gap`
+pub fn line_format_data(
+    bytes: &[Option<u8>],
+    blocksize: u16,
+    display: DisplayOptions,
+    gap: char,
+    style: impl Fn(usize) -> Style,
+) -> Line<'static> {
+    let mut spans = Vec::with_capacity(2 * bytes.len());
+    let group = display.group;
+    let width = display.cell_width();
+    let gap_cell = String::from(gap).repeat(width);
+    // blocks are made of whole words
+    let words_per_block = std::cmp::max(blocksize as usize / group, 1);
+
+    for (w, word) in bytes.chunks(group).enumerate() {
+        let start = w * group;
+        if w != 0 {
+            // separator between words, doubled between blocks
+            if w % words_per_block == 0 {
+                spans.push(Span::raw("  "));
+            } else {
+                spans.push(Span::raw(" "));
+            }
         }
+
+        let values = word.iter().copied().collect::<Option<Vec<u8>>>();
+        match values {
+            None if word.iter().all(Option::is_none) => {
+                spans.push(Span::styled(
+                    gap_cell.clone(),
+                    style(start).fg(Color::DarkGray),
+                ));
+            }
+            // digits of each byte, in the order of the word
+            Some(values)
+                if display.word_format == WordFormat::Raw
+                    && matches!(display.radix, Radix::Hexadecimal | Radix::Binary) =>
+            {
+                let mut cells = (0..values.len()).collect::<Vec<_>>();
+                if display.endian == Endian::Little {
+                    cells.reverse();
+                }
+                // a partial word at the end of the line is right-aligned when
+                // its missing bytes are the most significant ones
+                if display.endian == Endian::Little {
+                    let pad = " ".repeat((group - values.len()) * display.radix.cell_width());
+                    spans.push(Span::raw(pad));
+                }
+                for i in cells {
+                    spans.push(Span::styled(
+                        display.radix.format_byte(values[i], display.uppercase),
+                        style(start + i),
+                    ));
+                }
+            }
+            Some(values) if values.len() == group => {
+                // the cursor is shown on the whole word
+                let word_style = (start..start + group)
+                    .map(&style)
+                    .find(|style| style.add_modifier.contains(Modifier::REVERSED))
+                    .unwrap_or_else(|| style(start));
+                spans.push(Span::styled(display.format_word(&values), word_style));
+            }
+            // partial word, or partially in a gap: bytes are shown as is
+            _ => {
+                let digits = word
+                    .iter()
+                    .map(|b| match b {
+                        Some(b) => Radix::Hexadecimal.format_byte(*b, display.uppercase),
+                        None => String::from(gap).repeat(2),
+                    })
+                    .collect::<String>();
+                spans.push(Span::styled(
+                    format!("{digits:>width$}"),
+                    style(start).fg(Color::DarkGray),
+                ));
+            }
+        }
+    }
+
+    Line::from(spans)
+}
+
+/// Formats the decoded bytes of a line of the character pane
+pub fn line_format_chars(glyphs: &[Glyph], style: impl Fn(usize) -> Style) -> Line<'static> {
+    let mut spans = Vec::with_capacity(glyphs.len());
+
+    let mut i = 0;
+    while i < glyphs.len() {
+        let span = match glyphs[i] {
+            // a wide character covers the cell of its next byte
+            Glyph::Char(c) if c.width() == Some(2) => {
+                if glyphs.get(i + 1) == Some(&Glyph::Continuation) {
+                    spans.push(Span::styled(String::from(c), style(i)));
+                    i += 2;
+                    continue;
+                }
+                // no room left on the line
+                Span::styled(String::from('.'), style(i))
+            }
+            Glyph::Char(c) => Span::styled(String::from(c), style(i)),
+            Glyph::Continuation => Span::styled(String::from(CONTINUATION), style(i).dark_gray()),
+            Glyph::Invalid => Span::styled(String::from('.'), style(i)),
+            Glyph::Gap => Span::styled(String::from(GAP_ASCII), style(i)),
+        };
+        spans.push(span);
+        i += 1;
     }
 
-    s
+    Line::from(spans)
+}
+
+/// Number of digits of `val` written in `radix`
+pub fn count_digits(val: usize, radix: Radix) -> usize {
+    count_digits_u64(val as u64, radix)
 }
 
-pub fn count_hexdigits(val: usize) -> usize {
+fn count_digits_u64(mut val: u64, radix: Radix) -> usize {
     let mut i = 0;
-    while val >> (4 * i) != 0 {
+    while val != 0 {
+        val /= radix.base() as u64;
         i += 1;
     }
 
@@ -91,26 +625,61 @@ pub fn count_hexdigits(val: usize) -> usize {
 
 /// Explanation of the calculation
 ///
-/// |-Addr-----------|-Hex-------------------------------|-Ascii---------------|
-/// |                |                                   |                     |
-/// |                |                                   |                     |
-/// | PAD + ADDR_LEN |  3*nb_bytes+nb_bytes/blocksize-1  |       nb_bytes      |
-/// | <------------> | <-------------------------------> | <-----------------> |
-/// |                |                                   |                     |
-/// |                |                                   |                     |
-/// |                |                                   |                     |
-/// |----------------|-----------------------------------|---------------------|
+/// |-Addr-----------|-Data----------------------------------|-Ascii-----------|
+/// |                |                                       |                 |
+/// |                |                                       |                 |
+/// | PAD + ADDR_LEN | (C+1)*nb_words+nb_words/block_words-1 |  G * nb_words   |
+/// | <------------> | <-----------------------------------> | <-------------> |
+/// |                |                                       |                 |
+/// |                |                                       |                 |
+/// |                |                                       |                 |
+/// |----------------|---------------------------------------|-----------------|
 ///
 ///
-/// width = (ADDR_WIDTH) + (3*nb_bytes + nb_bytes/blocksize - 1) + nb_bytes + 3
+/// width = (ADDR_WIDTH) + ((C+1)*nb_words + nb_words/block_words - 1) + G*nb_words + 3
 ///
 /// ADDR_WIDTH = PAD + ADDR_LEN
+/// G is the number of bytes per word (1 unless grouped)
+/// C is the width of a word in the data pane (2 for a byte in hexadecimal)
+/// block_words = blocksize / G, the number of words per block
 /// 3 is the number of bytes allocated to ratatui interface
 ///
-/// Reverse the formula in order to get `nb_bytes`:
+/// Reverse the formula in order to get `nb_words`:
 ///
-///  nb_bytes = floor((blocksize)/(4*blocksize+1) * (width - 3 + 1 - PAD - ADDR_LEN))
-pub fn get_bytes_per_row(width: u16, addr_width: u16, blocksize: u16) -> usize {
-    (blocksize * ((width - addr_width - 3 /* ratatui needed bytes */ + 1) / (4 * blocksize + 1)))
-        as usize
+///  nb_words = floor((block_words)/((C+1+G)*block_words+1) * (width - 3 + 1 - PAD - ADDR_LEN))
+///
+/// and the number of bytes is `G * nb_words`, at least one block even if it
+/// does not fit in `width`.
+pub fn get_bytes_per_row(
+    width: u16,
+    addr_width: u16,
+    blocksize: u16,
+    cell_width: u16,
+    group: u16,
+) -> usize {
+    let block_words = std::cmp::max(blocksize / group, 1);
+    let nb_blocks = (width.saturating_sub(addr_width + 3 /* ratatui needed bytes */) + 1)
+        / ((cell_width + 1 + group) * block_words + 1);
+    (group * block_words * std::cmp::max(nb_blocks, 1)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_per_row_fills_the_width() {
+        // 3 blocks of 8 bytes: 8 * (2 + 1) + 1 in hex, 8 in ascii
+        assert_eq!(get_bytes_per_row(120, 10, 8, 2, 1), 24);
+        // a single block in binary, 8 + 1 columns per byte in the data pane
+        assert_eq!(get_bytes_per_row(120, 10, 8, 8, 1), 8);
+        // 4 blocks of 2 words of 4 bytes
+        assert_eq!(get_bytes_per_row(120, 10, 8, 8, 4), 32);
+    }
+
+    #[test]
+    fn bytes_per_row_holds_a_block_when_narrow() {
+        assert_eq!(get_bytes_per_row(60, 10, 16, 2, 1), 16);
+        assert_eq!(get_bytes_per_row(5, 10, 64, 2, 1), 64);
+    }
 }
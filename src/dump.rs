@@ -0,0 +1,318 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::{
+    display::{self, DisplayOptions, Endian, Radix, WordFormat},
+    encoding::{CharTable, Encoding, Glyph},
+};
+
+/// Layout of the lines written by `--dump`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DumpFormat {
+    /// Address, data and character panes of the interface
+    #[default]
+    Hexplore,
+    /// Same output as `xxd`
+    Xxd,
+    /// Same output as `hexdump -C`
+    Hexdump,
+    /// Same output as `od`
+    Od,
+    /// Hexadecimal bytes only, same output as `xxd -p`
+    Plain,
+}
+
+/// Range and formatting of a dump, inherited from cli flags
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    pub format: DumpFormat,
+    /// First dumped byte
+    pub offset: usize,
+    /// Number of dumped bytes, up to the end of the file if `None`
+    pub length: Option<usize>,
+    /// Bytes per line, the format default if `None`
+    pub cols: Option<u16>,
+    pub blocksize: Option<u16>,
+    /// Formatting of the `hexplore` format, the others having their own
+    pub display: DisplayOptions,
+    pub char_table: Option<CharTable>,
+}
+
+impl DumpOptions {
+    /// Formatting of the data pane imitating the tool of the format
+    fn display(&self) -> DisplayOptions {
+        let preset = |group, endian, radix| DisplayOptions {
+            radix,
+            group,
+            endian,
+            word_format: WordFormat::Raw,
+            encoding: Encoding::Ascii,
+            uppercase: false,
+            uppercase_address: false,
+            ..DisplayOptions::default()
+        };
+        match self.format {
+            DumpFormat::Hexplore => self.display,
+            DumpFormat::Xxd => preset(2, Endian::Big, Radix::Hexadecimal),
+            DumpFormat::Hexdump | DumpFormat::Plain => {
+                preset(1, Endian::Little, Radix::Hexadecimal)
+            }
+            DumpFormat::Od => preset(2, Endian::Little, Radix::Octal),
+        }
+    }
+
+    /// Radix of the addresses, followed by a `:` for `xxd`
+    pub fn address_radix(&self) -> Radix {
+        match self.format {
+            DumpFormat::Hexplore => self.display.address_radix,
+            DumpFormat::Xxd | DumpFormat::Hexdump | DumpFormat::Plain => Radix::Hexadecimal,
+            DumpFormat::Od => Radix::Octal,
+        }
+    }
+
+    fn cols(&self) -> usize {
+        let default = match self.format {
+            DumpFormat::Plain => 30,
+            _ => 16,
+        };
+        self.cols.unwrap_or(default) as usize
+    }
+
+    fn blocksize(&self) -> u16 {
+        match (self.blocksize, self.format) {
+            (Some(blocksize), _) => blocksize,
+            (None, DumpFormat::Hexplore | DumpFormat::Hexdump) => 8,
+            // words separated by a single space
+            (None, DumpFormat::Xxd | DumpFormat::Od | DumpFormat::Plain) => {
+                self.cols().next_multiple_of(2) as u16
+            }
+        }
+    }
+}
+
+/// Writes the hexdump of `content` to `out`, without the interface
+pub fn dump(out: &mut impl Write, content: &[u8], options: &DumpOptions) -> io::Result<()> {
+    let start = std::cmp::min(options.offset, content.len());
+    let end = match options.length {
+        Some(length) => std::cmp::min(start.saturating_add(length), content.len()),
+        None => content.len(),
+    };
+    let cols = options.cols();
+    let blocksize = options.blocksize();
+    let display = options.display();
+    // width of a full line, the last one being padded
+    let width = display::line_format_data(
+        &vec![Some(0); cols],
+        blocksize,
+        display,
+        display::GAP_DATA,
+        |_| Default::default(),
+    )
+    .width();
+    // the characters are decoded at once, some of them spanning several lines
+    let cells = content[start..end]
+        .iter()
+        .map(|&b| Some(b))
+        .collect::<Vec<_>>();
+    let mut glyphs = display
+        .encoding
+        .decode(&cells, start, options.char_table.as_ref());
+    // `xxd` and `hexdump` show spaces, which the character pane does not
+    if display.encoding == Encoding::Ascii
+        && matches!(options.format, DumpFormat::Xxd | DumpFormat::Hexdump)
+    {
+        for (glyph, &b) in glyphs.iter_mut().zip(&content[start..end]) {
+            if b == b' ' {
+                *glyph = Glyph::Char(' ');
+            }
+        }
+    }
+    let radix = options.address_radix();
+    let addr_width = match options.format {
+        DumpFormat::Hexplore => std::cmp::max(display::count_digits(end, radix), 1),
+        DumpFormat::Xxd | DumpFormat::Hexdump | DumpFormat::Plain => 8,
+        DumpFormat::Od => 7,
+    };
+    let address = |addr: usize| radix.format_address(addr, addr_width, display.uppercase_address);
+
+    let mut previous: Option<&[u8]> = None;
+    let mut squeezing = false;
+    for (i, line) in content[start..end].chunks(cols).enumerate() {
+        let addr = start + i * cols;
+        // `hexdump` and `od` replace repeated lines with a single `*`
+        if matches!(options.format, DumpFormat::Hexdump | DumpFormat::Od)
+            && line.len() == cols
+            && previous == Some(line)
+        {
+            if !squeezing {
+                writeln!(out, "*")?;
+                squeezing = true;
+            }
+            continue;
+        }
+        previous = Some(line);
+        squeezing = false;
+
+        let mut cells = line.iter().map(|&b| Some(b)).collect::<Vec<_>>();
+        if options.format == DumpFormat::Od {
+            // the last word is padded with zeros
+            cells.resize(line.len().next_multiple_of(display.group), Some(0));
+        }
+        let data = display::line_format_data(&cells, blocksize, display, display::GAP_DATA, |_| {
+            Default::default()
+        });
+        let chars = display::line_format_chars(&glyphs[i * cols..i * cols + line.len()], |_| {
+            Default::default()
+        });
+        let addr = address(addr);
+        match options.format {
+            DumpFormat::Hexplore => writeln!(out, "{addr}  {:<width$}  {chars}", data.to_string())?,
+            DumpFormat::Xxd => writeln!(out, "{addr}: {:<width$}  {chars}", data.to_string())?,
+            DumpFormat::Hexdump => {
+                writeln!(out, "{addr}  {:<width$}  |{chars}|", data.to_string())?
+            }
+            DumpFormat::Od => writeln!(out, "{addr} {data}")?,
+            DumpFormat::Plain => {
+                let digits = line
+                    .iter()
+                    .map(|&b| Radix::Hexadecimal.format_byte(b, false))
+                    .collect::<String>();
+                writeln!(out, "{digits}")?
+            }
+        }
+    }
+
+    // `hexdump` and `od` end with the address following the dumped bytes
+    match options.format {
+        DumpFormat::Hexdump if end > start => writeln!(out, "{}", address(end))?,
+        DumpFormat::Od => writeln!(out, "{}", address(end))?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A greeting, 50 zeros and a last partial line
+    fn content() -> Vec<u8> {
+        [&b"Hello, world!\n"[..], &[0; 50], b"end"].concat()
+    }
+
+    fn dump_to_string(content: &[u8], options: DumpOptions) -> String {
+        let mut out = vec![];
+        dump(&mut out, content, &options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn options(format: DumpFormat) -> DumpOptions {
+        DumpOptions {
+            format,
+            ..DumpOptions::default()
+        }
+    }
+
+    #[test]
+    fn xxd() {
+        let expected = "\
+00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a 0000  Hello, world!...
+00000010: 0000 0000 0000 0000 0000 0000 0000 0000  ................
+00000020: 0000 0000 0000 0000 0000 0000 0000 0000  ................
+00000030: 0000 0000 0000 0000 0000 0000 0000 0000  ................
+00000040: 656e 64                                  end
+";
+        assert_eq!(
+            dump_to_string(&content(), options(DumpFormat::Xxd)),
+            expected
+        );
+    }
+
+    #[test]
+    fn xxd_cols_offset_length() {
+        // xxd -c 8 -s 3 -l 20
+        let expected = "\
+00000003: 6c6f 2c20 776f 726c  lo, worl
+0000000b: 6421 0a00 0000 0000  d!......
+00000013: 0000 0000            ....
+";
+        let options = DumpOptions {
+            cols: Some(8),
+            offset: 3,
+            length: Some(20),
+            ..options(DumpFormat::Xxd)
+        };
+        assert_eq!(dump_to_string(&content(), options), expected);
+    }
+
+    #[test]
+    fn xxd_plain() {
+        let expected = "\
+48656c6c6f2c20776f726c64210a00000000000000000000000000000000
+000000000000000000000000000000000000000000000000000000000000
+00000000656e64
+";
+        assert_eq!(
+            dump_to_string(&content(), options(DumpFormat::Plain)),
+            expected
+        );
+    }
+
+    #[test]
+    fn hexdump_squeezes_repeated_lines() {
+        let expected = "\
+00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 00  |Hello, world!...|
+00000010  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|
+*
+00000040  65 6e 64                                          |end|
+00000043
+";
+        assert_eq!(
+            dump_to_string(&content(), options(DumpFormat::Hexdump)),
+            expected
+        );
+    }
+
+    #[test]
+    fn od_squeezes_repeated_lines() {
+        let expected = "\
+0000000 062510 066154 026157 073440 071157 062154 005041 000000
+0000020 000000 000000 000000 000000 000000 000000 000000 000000
+*
+0000100 067145 000144
+0000103
+";
+        assert_eq!(
+            dump_to_string(&content(), options(DumpFormat::Od)),
+            expected
+        );
+    }
+
+    #[test]
+    fn od_cols_offset_length() {
+        // od -w8 -j 3 -N 21, the last word being padded with zeros
+        let expected = "\
+0000003 067554 020054 067567 066162
+0000013 020544 000012 000000 000000
+0000023 000000 000000 000000
+0000030
+";
+        let options = DumpOptions {
+            cols: Some(8),
+            offset: 3,
+            length: Some(21),
+            ..options(DumpFormat::Od)
+        };
+        assert_eq!(dump_to_string(&content(), options), expected);
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(dump_to_string(b"", options(DumpFormat::Xxd)), "");
+        assert_eq!(dump_to_string(b"", options(DumpFormat::Plain)), "");
+        assert_eq!(dump_to_string(b"", options(DumpFormat::Hexdump)), "");
+        assert_eq!(dump_to_string(b"", options(DumpFormat::Od)), "0000000\n");
+    }
+}
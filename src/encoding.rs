@@ -0,0 +1,471 @@
+use std::{fs, path::Path};
+
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthChar;
+
+/// Decoding of the bytes shown in the character pane
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    #[default]
+    Ascii,
+    #[value(name = "latin1")]
+    Latin1,
+    Cp437,
+    #[value(name = "windows1252")]
+    Windows1252,
+    Cp037,
+    Cp500,
+    #[value(name = "utf8")]
+    Utf8,
+    #[value(name = "utf16le")]
+    Utf16Le,
+    #[value(name = "utf16be")]
+    Utf16Be,
+    /// Table loaded with `--table`
+    Custom,
+}
+
+impl Encoding {
+    /// Next encoding, the custom one being skipped when no table is loaded
+    pub fn next(self, has_table: bool) -> Self {
+        match self {
+            Encoding::Ascii => Encoding::Latin1,
+            Encoding::Latin1 => Encoding::Cp437,
+            Encoding::Cp437 => Encoding::Windows1252,
+            Encoding::Windows1252 => Encoding::Cp037,
+            Encoding::Cp037 => Encoding::Cp500,
+            Encoding::Cp500 => Encoding::Utf8,
+            Encoding::Utf8 => Encoding::Utf16Le,
+            Encoding::Utf16Le => Encoding::Utf16Be,
+            Encoding::Utf16Be if has_table => Encoding::Custom,
+            Encoding::Utf16Be | Encoding::Custom => Encoding::Ascii,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Encoding::Ascii => "Ascii",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Cp437 => "CP437",
+            Encoding::Windows1252 => "Windows-1252",
+            Encoding::Cp037 => "EBCDIC 037",
+            Encoding::Cp500 => "EBCDIC 500",
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Custom => "Table",
+        }
+    }
+
+    /// Number of bytes before and after a line needed to decode characters
+    /// spanning several lines
+    pub const CONTEXT: usize = 4;
+
+    /// Decodes the bytes of a window of the file starting at `start`, one
+    /// glyph per byte
+    pub fn decode(
+        &self,
+        bytes: &[Option<u8>],
+        start: usize,
+        table: Option<&CharTable>,
+    ) -> Vec<Glyph> {
+        let single = |map: &dyn Fn(u8) -> Glyph| {
+            bytes
+                .iter()
+                .map(|b| b.map_or(Glyph::Gap, map))
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Encoding::Ascii => single(&|b| match b {
+                b if b.is_ascii_graphic() => Glyph::Char(b.into()),
+                _ => Glyph::Invalid,
+            }),
+            Encoding::Latin1 => single(&|b| printable(b.into())),
+            Encoding::Cp437 => single(&|b| Glyph::Char(CP437[b as usize])),
+            Encoding::Windows1252 => single(&|b| match b {
+                0x80..=0x9f => printable(WINDOWS_1252_HIGH[b as usize - 0x80]),
+                _ => printable(b.into()),
+            }),
+            Encoding::Cp037 => single(&|b| printable(CP037[b as usize])),
+            Encoding::Cp500 => single(&|b| printable(CP500[b as usize])),
+            Encoding::Custom => {
+                single(&|b| match table.and_then(|table| table.chars[b as usize]) {
+                    Some(c) => printable(c),
+                    None => Glyph::Invalid,
+                })
+            }
+            Encoding::Utf8 => decode_utf8(bytes),
+            Encoding::Utf16Le => decode_utf16(bytes, start, u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(bytes, start, u16::from_be_bytes),
+        }
+    }
+
+    /// Decodes the bytes of the line starting at `offset` of the file, along
+    /// with the end of the `previous` line and the start of the `next` one
+    /// for the characters spanning several lines
+    pub fn decode_line(
+        &self,
+        previous: &[Option<u8>],
+        cells: &[Option<u8>],
+        next: &[Option<u8>],
+        offset: usize,
+        table: Option<&CharTable>,
+    ) -> Vec<Glyph> {
+        // keep the start of the window even, for UTF-16
+        let before = std::cmp::min(offset, Self::CONTEXT - offset % 2);
+        let before = std::cmp::min(before, previous.len());
+        let mut window = previous[previous.len() - before..].to_vec();
+        window.extend_from_slice(cells);
+        window.extend(next.iter().take(Self::CONTEXT));
+
+        let glyphs = self.decode(&window, offset - before, table);
+        glyphs[before..before + cells.len()].to_vec()
+    }
+}
+
+/// What is displayed for a byte in the character pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Glyph {
+    Char(char),
+    /// Byte of a multi-byte character displayed on a previous byte
+    Continuation,
+    /// Non printable character, or byte not decoded
+    Invalid,
+    /// Gap of the aligned diff view or unreadable byte
+    Gap,
+}
+
+fn printable(c: char) -> Glyph {
+    match c.width() {
+        // soft hyphen, only drawn at a line break
+        _ if c == '\u{ad}' => Glyph::Invalid,
+        Some(1 | 2) => Glyph::Char(c),
+        // control characters and combining marks
+        _ => Glyph::Invalid,
+    }
+}
+
+fn decode_utf8(bytes: &[Option<u8>]) -> Vec<Glyph> {
+    let mut glyphs = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = match bytes[i] {
+            None => {
+                glyphs.push(Glyph::Gap);
+                i += 1;
+                continue;
+            }
+            Some(0xc2..=0xdf) => 2,
+            Some(0xe0..=0xef) => 3,
+            Some(0xf0..=0xf4) => 4,
+            Some(_) => 1,
+        };
+        let sequence = bytes[i..]
+            .iter()
+            .take(len)
+            .copied()
+            .collect::<Option<Vec<u8>>>();
+        match sequence.as_deref().map(std::str::from_utf8) {
+            Some(Ok(s)) if s.len() == len => {
+                let c = s.chars().next().unwrap_or_default();
+                glyphs.push(printable(c));
+                glyphs.extend(std::iter::repeat_n(Glyph::Continuation, len - 1));
+                i += len;
+            }
+            // invalid or truncated sequence
+            _ => {
+                glyphs.push(Glyph::Invalid);
+                i += 1;
+            }
+        }
+    }
+
+    glyphs
+}
+
+/// Decodes UTF-16 code units, aligned on even offsets of the file
+fn decode_utf16(bytes: &[Option<u8>], start: usize, unit: fn([u8; 2]) -> u16) -> Vec<Glyph> {
+    let mut glyphs = Vec::with_capacity(bytes.len());
+    let unit_at = |i: usize| match (bytes.get(i), bytes.get(i + 1)) {
+        (Some(Some(a)), Some(Some(b))) => Some(unit([*a, *b])),
+        _ => None,
+    };
+    let mut i = 0;
+    if start % 2 == 1 {
+        glyphs.push(Glyph::Invalid);
+        i += 1;
+    }
+    while i < bytes.len() {
+        let Some(first) = unit_at(i) else {
+            // gap, or last byte of an odd-sized file
+            let glyph = match bytes[i] {
+                None => Glyph::Gap,
+                Some(_) => Glyph::Invalid,
+            };
+            glyphs.push(glyph);
+            i += 1;
+            continue;
+        };
+        let (c, len) = match char::decode_utf16([first, unit_at(i + 2).unwrap_or_default()]).next()
+        {
+            Some(Ok(c)) if c.len_utf16() == 2 => (Some(c), 4),
+            Some(Ok(c)) => (Some(c), 2),
+            // lone surrogate
+            _ => (None, 2),
+        };
+        match c {
+            Some(c) => {
+                glyphs.push(printable(c));
+                glyphs.extend(std::iter::repeat_n(Glyph::Continuation, len - 1));
+            }
+            None => glyphs.extend([Glyph::Invalid; 2]),
+        }
+        i += len;
+    }
+    glyphs.truncate(bytes.len());
+
+    glyphs
+}
+
+/// User supplied decoding of the bytes, e.g. the text encoding of a game ROM
+#[derive(Debug, Clone)]
+pub struct CharTable {
+    pub name: String,
+    chars: [Option<char>; 256],
+}
+
+impl CharTable {
+    /// Loads a table file made of `XX=c` lines, `XX` being the byte in
+    /// hexadecimal and `c` its character. Empty lines and lines starting with
+    /// `#` are ignored, as well as the bytes missing from the table.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+        let mut chars = [None; 256];
+        for (i, line) in content.lines().enumerate() {
+            let err = |msg: &str| format!("{path}:{}: {msg}", i + 1);
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (byte, c) = line.split_once('=').ok_or_else(|| err("expected XX=c"))?;
+            let byte = u8::from_str_radix(byte.trim(), 16)
+                .map_err(|_| err(&format!("'{byte}' is not a byte in hexadecimal")))?;
+            let mut c_chars = c.chars();
+            let (Some(c), None) = (c_chars.next(), c_chars.next()) else {
+                return Err(err(&format!("'{c}' is not a single character")));
+            };
+            chars[byte as usize] = Some(c);
+        }
+
+        let name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string());
+        Ok(CharTable { name, chars })
+    }
+}
+
+/// IBM PC code page, with the glyphs of the control characters (NUL
+/// excepted, which would look like a space)
+#[rustfmt::skip]
+const CP437: [char; 256] = [
+    '.', '☺', '☻', '♥', '♦', '♣', '♠', '•', '◘', '○', '◙', '♂', '♀', '♪', '♫', '☼',
+    '►', '◄', '↕', '‼', '¶', '§', '▬', '↨', '↑', '↓', '→', '←', '∟', '↔', '▲', '▼',
+    ' ', '!', '"', '#', '$', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    '@', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '[', '\\', ']', '^', '_',
+    '`', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '{', '|', '}', '~', '⌂',
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// Windows-1252 characters from 0x80 to 0x9F, the others being Latin-1
+#[rustfmt::skip]
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '€', '.', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '.', 'Ž', '.',
+    '.', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '.', 'ž', 'Ÿ',
+];
+
+/// EBCDIC code page of the US and Canada
+#[rustfmt::skip]
+const CP037: [char; 256] = [
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    ' ', '\u{a0}', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '¢', '.', '<', '(', '+', '|',
+    '&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', '!', '$', '*', ')', ';', '¬',
+    '-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?',
+    'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', '`', ':', '#', '@', '\'', '=', '"',
+    'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±',
+    '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤',
+    'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®',
+    '^', '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '[', ']', '¯', '¨', '´', '×',
+    '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', '\u{ad}', 'ô', 'ö', 'ò', 'ó', 'õ',
+    '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ',
+    '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '.',
+];
+
+/// International EBCDIC code page
+#[rustfmt::skip]
+const CP500: [char; 256] = [
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.', '.',
+    ' ', '\u{a0}', 'â', 'ä', 'à', 'á', 'ã', 'å', 'ç', 'ñ', '[', '.', '<', '(', '+', '!',
+    '&', 'é', 'ê', 'ë', 'è', 'í', 'î', 'ï', 'ì', 'ß', ']', '$', '*', ')', ';', '^',
+    '-', '/', 'Â', 'Ä', 'À', 'Á', 'Ã', 'Å', 'Ç', 'Ñ', '¦', ',', '%', '_', '>', '?',
+    'ø', 'É', 'Ê', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', '`', ':', '#', '@', '\'', '=', '"',
+    'Ø', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', '«', '»', 'ð', 'ý', 'þ', '±',
+    '°', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 'ª', 'º', 'æ', '¸', 'Æ', '¤',
+    'µ', '~', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '¡', '¿', 'Ð', 'Ý', 'Þ', '®',
+    '¢', '£', '¥', '·', '©', '§', '¶', '¼', '½', '¾', '¬', '|', '¯', '¨', '´', '×',
+    '{', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', '\u{ad}', 'ô', 'ö', 'ò', 'ó', 'õ',
+    '}', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', '¹', 'û', 'ü', 'ù', 'ú', 'ÿ',
+    '\\', '÷', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '²', 'Ô', 'Ö', 'Ò', 'Ó', 'Õ',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '³', 'Û', 'Ü', 'Ù', 'Ú', '.',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(encoding: Encoding, bytes: &[u8]) -> Vec<Glyph> {
+        let cells = bytes.iter().map(|&b| Some(b)).collect::<Vec<_>>();
+        encoding.decode(&cells, 0, None)
+    }
+
+    /// Characters displayed for `bytes`, `.` for the invalid ones
+    fn text(encoding: Encoding, bytes: &[u8]) -> String {
+        decode(encoding, bytes)
+            .into_iter()
+            .map(|glyph| match glyph {
+                Glyph::Char(c) => c,
+                Glyph::Continuation => '_',
+                Glyph::Invalid => '.',
+                Glyph::Gap => ' ',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ascii_shows_graphic_characters_only() {
+        assert_eq!(text(Encoding::Ascii, b"a b\t~\x7f\x80"), "a.b.~..");
+        assert_eq!(Encoding::default(), Encoding::Ascii);
+    }
+
+    #[test]
+    fn code_pages() {
+        let bytes = [0x01, 0x41, 0x7f, 0x80, 0x9b, 0xb0, 0xe1, 0xff];
+        assert_eq!(text(Encoding::Cp437, &bytes), "☺A⌂Ç¢░ß\u{a0}");
+        assert_eq!(text(Encoding::Latin1, &[0x41, 0x85, 0xe9, 0xad]), "A.é.");
+        // undefined bytes of Windows-1252 are shown as dots
+        assert_eq!(
+            text(
+                Encoding::Windows1252,
+                &[0x41, 0x80, 0x81, 0x85, 0x99, 0x9f, 0xe9]
+            ),
+            "A€.…™Ÿé"
+        );
+        // "Hello!" in EBCDIC, then the characters swapped between 037 and 500
+        let hello = [0xc8, 0x85, 0x93, 0x93, 0x96, 0x5a];
+        assert_eq!(text(Encoding::Cp037, &hello), "Hello!");
+        assert_eq!(text(Encoding::Cp500, &hello), "Hello]");
+        let swapped = [0x4a, 0x4f, 0x5a, 0x5f, 0xb0, 0xba, 0xbb];
+        assert_eq!(text(Encoding::Cp037, &swapped), "¢|!¬^[]");
+        assert_eq!(text(Encoding::Cp500, &swapped), "[!]^¢¬|");
+        // controls and the soft hyphen
+        for encoding in [Encoding::Cp037, Encoding::Cp500] {
+            assert_eq!(
+                text(encoding, &[0x00, 0x15, 0x40, 0xca, 0xff]),
+                ".. ..",
+                "{encoding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn utf8() {
+        assert_eq!(text(Encoding::Utf8, "aé€😀".as_bytes()), "aé_€__😀___");
+        // invalid, overlong and truncated sequences
+        assert_eq!(
+            text(Encoding::Utf8, &[0xff, 0xc0, 0x80, 0xe2, 0x82]),
+            "....."
+        );
+        let cells = [Some(0xc3), None, Some(0xa9)];
+        assert_eq!(
+            Encoding::Utf8.decode(&cells, 0, None),
+            [Glyph::Invalid, Glyph::Gap, Glyph::Invalid]
+        );
+    }
+
+    #[test]
+    fn utf16() {
+        let le = "a€😀"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let be = "a€😀"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(text(Encoding::Utf16Le, &le), "a_€_😀___");
+        assert_eq!(text(Encoding::Utf16Be, &be), "a_€_😀___");
+        // lone surrogate and odd size
+        assert_eq!(
+            text(Encoding::Utf16Le, &[0x00, 0xd8, 0x61, 0x00, 0x62]),
+            "..a_."
+        );
+        // decoding starts on an odd offset of the file
+        let cells = le.iter().map(|&b| Some(b)).collect::<Vec<_>>();
+        assert_eq!(
+            Encoding::Utf16Le.decode(&cells[1..], 1, None)[..3],
+            [Glyph::Invalid, Glyph::Char('€'), Glyph::Continuation]
+        );
+    }
+
+    /// Glyphs of the lines of `bytes` split every `cols` bytes
+    fn lines(encoding: Encoding, bytes: &[u8], cols: usize) -> Vec<Vec<Glyph>> {
+        let cells = bytes.iter().map(|&b| Some(b)).collect::<Vec<_>>();
+        let lines = cells.chunks(cols).collect::<Vec<_>>();
+        (0..lines.len())
+            .map(|i| {
+                let previous = i.checked_sub(1).map_or(&[][..], |i| lines[i]);
+                let next = lines.get(i + 1).copied().unwrap_or_default();
+                encoding.decode_line(previous, lines[i], next, i * cols, None)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn characters_spanning_lines() {
+        // '€' starts on the last byte of the first line
+        let bytes = [&b"abc"[..], "€".as_bytes(), b"d"].concat();
+        let glyphs = lines(Encoding::Utf8, &bytes, 4);
+        assert_eq!(glyphs.concat(), decode(Encoding::Utf8, &bytes));
+        assert_eq!(glyphs[0][3], Glyph::Char('€'));
+        assert_eq!(
+            glyphs[1],
+            [Glyph::Continuation, Glyph::Continuation, Glyph::Char('d')]
+        );
+
+        // a surrogate pair split between two lines, on odd line lengths
+        let le = "ab😀c"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        for cols in [3, 5, 6, 7] {
+            let glyphs = lines(Encoding::Utf16Le, &le, cols);
+            assert_eq!(glyphs.concat(), decode(Encoding::Utf16Le, &le), "{cols}");
+        }
+    }
+}
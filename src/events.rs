@@ -1,26 +1,78 @@
 use crate::app::App;
+use crate::clipboard;
+use crate::display::{Endian, Radix};
+use crate::hash::ALGORITHMS;
 use log::debug;
-use ratatui::crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind};
+use ratatui::crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
 
 impl App {
     pub fn handle_event(&mut self, event: Event) {
-        let filesize = self.fileinfo.size;
         match event {
+            // Command prompt grabs the keyboard while open
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.command.is_some() => {
+                self.handle_command_key(key);
+            }
+            // Mapping list grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.show_mappings => {
+                self.handle_mappings_key(key);
+            }
+            // Member list grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.show_members => {
+                self.handle_members_key(key);
+            }
+            // Bookmark list grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.show_bookmarks => {
+                self.handle_bookmarks_key(key);
+            }
+            // Buffer list grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.show_buffers => {
+                self.handle_buffers_key(key);
+            }
+            // Strings panel grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.buffer.strings.visible => {
+                self.handle_strings_key(key);
+            }
+            // File details popup grabs the keyboard while visible
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.show_fileinfo => {
+                self.handle_fileinfo_key(key);
+            }
+            // Second key of a two keys command
+            Event::Key(key) if key.kind == KeyEventKind::Press && self.pending_key.is_some() => {
+                let prefix = self.pending_key.take();
+                match (prefix, key.code) {
+                    (Some(']'), KeyCode::Char('c')) => self.move_to_difference(true),
+                    (Some('['), KeyCode::Char('c')) => self.move_to_difference(false),
+                    (Some(']'), KeyCode::Char('m')) => self.move_to_range(true),
+                    (Some('['), KeyCode::Char('m')) => self.move_to_range(false),
+                    (Some('g'), KeyCode::Char('g')) => self.move_cursor_to(0),
+                    (Some('g'), KeyCode::Char('t')) => self.next_buffer(),
+                    (Some('g'), KeyCode::Char('T')) => self.previous_buffer(),
+                    // Marks, vim style
+                    (Some('m'), KeyCode::Char(c)) if c.is_ascii_alphabetic() => {
+                        self.set_bookmark(&c.to_string(), "");
+                    }
+                    (Some('\''), KeyCode::Char(c)) => self.jump_to_bookmark(&c.to_string()),
+                    _ => {}
+                }
+            }
             Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.message = None;
                 match (key.code, key.modifiers) {
                     // Exit
                     (KeyCode::Char('q'), KeyModifiers::NONE) => self.quit = true,
                     // Navigation (vim style)
                     //   Down
-                    (KeyCode::Char('j'), KeyModifiers::NONE) => {
-                        if (self.scroll_pos + 1) * self.bytes_per_row < filesize {
-                            self.scroll_pos += 1;
-                        }
-                    }
+                    (KeyCode::Char('j'), KeyModifiers::NONE)
+                    | (KeyCode::Down, KeyModifiers::NONE) => self.move_cursor_down(),
                     //   Up
-                    (KeyCode::Char('k'), KeyModifiers::NONE) => {
-                        self.scroll_pos = self.scroll_pos.saturating_sub(1);
-                    }
+                    (KeyCode::Char('k'), KeyModifiers::NONE)
+                    | (KeyCode::Up, KeyModifiers::NONE) => self.move_cursor_up(),
+                    //   Left
+                    (KeyCode::Left, KeyModifiers::NONE) => self.move_cursor_left(),
+                    //   Right
+                    (KeyCode::Right, KeyModifiers::NONE) => self.move_cursor_right(),
                     //   Mid-page up
                     (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
                         self.move_page_half_up();
@@ -37,32 +89,139 @@ impl App {
                     (KeyCode::PageDown, KeyModifiers::NONE) => {
                         self.move_page_down();
                     }
-                    //   go to start
-                    (KeyCode::Char('g'), KeyModifiers::NONE) => {
-                        self.scroll_pos = 0;
-                    }
                     //   SHIFT + G -- go to end
                     (KeyCode::Char('G'), KeyModifiers::SHIFT) => {
-                        self.scroll_pos = (filesize - 1) / self.bytes_per_row;
+                        self.move_cursor_to(self.data_size())
                     }
+                    // Selection
+                    (KeyCode::Char('v'), KeyModifiers::NONE) => self.toggle_selection(),
+                    (KeyCode::Esc, KeyModifiers::NONE) => self.buffer.selection_anchor = None,
                     // Toggle help dialog
                     (KeyCode::Char('h'), KeyModifiers::NONE) => self.show_help = !self.show_help,
                     // Toggle file details dialog
                     (KeyCode::Char('i'), KeyModifiers::NONE) => {
                         self.show_fileinfo = !self.show_fileinfo;
                     }
+                    // Toggle statistics dialog
+                    (KeyCode::Char('s'), KeyModifiers::NONE) => {
+                        self.show_stats = !self.show_stats;
+                        self.refresh_stats();
+                    }
+                    //   SHIFT + S -- open strings panel
+                    (KeyCode::Char('S'), KeyModifiers::SHIFT) => {
+                        self.buffer.strings.visible = true;
+                        if self.buffer.strings.strings.is_none() {
+                            self.refresh_strings();
+                        }
+                    }
+                    // Prefix of two keys commands
+                    (KeyCode::Char(c @ (']' | '[' | 'g' | 'm' | '\'')), KeyModifiers::NONE) => {
+                        self.pending_key = Some(c);
+                    }
+                    //   SHIFT + D -- toggle diff summary
+                    (KeyCode::Char('D'), KeyModifiers::SHIFT) if self.buffer.diff.is_some() => {
+                        self.show_diff = !self.show_diff;
+                    }
+                    // Toggle minimap (overview)
+                    (KeyCode::Char('o'), KeyModifiers::NONE) => {
+                        self.buffer.minimap.visible = !self.buffer.minimap.visible;
+                    }
+                    //   SHIFT + O -- switch minimap mode
+                    (KeyCode::Char('O'), KeyModifiers::SHIFT) => self.buffer.minimap.toggle_mode(),
+                    // List bookmarks
+                    (KeyCode::Char('b'), KeyModifiers::NONE) => {
+                        self.bookmarks_selected = 0;
+                        self.show_bookmarks = true;
+                    }
+                    //   SHIFT + B -- list opened buffers
+                    (KeyCode::Char('B'), KeyModifiers::SHIFT) => {
+                        self.buffers_selected = self.current;
+                        self.show_buffers = true;
+                    }
+                    //   SHIFT + P -- list memory mappings of a process
+                    (KeyCode::Char('P'), KeyModifiers::SHIFT)
+                        if !self.buffer.fileinfo.mappings.is_empty() =>
+                    {
+                        self.show_mappings = true;
+                    }
+                    //   SHIFT + L -- list members of an archive
+                    (KeyCode::Char('L'), KeyModifiers::SHIFT) => self.show_archive(),
+                    // Switch data radix
+                    (KeyCode::Char('r'), KeyModifiers::NONE) => {
+                        self.display.radix = self.display.radix.next();
+                    }
+                    //   SHIFT + E -- switch encoding of the character pane
+                    (KeyCode::Char('E'), KeyModifiers::SHIFT) => {
+                        let has_table = self.char_table.is_some();
+                        self.display.encoding = self.display.encoding.next(has_table);
+                    }
+                    // Switch word size of the data pane
+                    (KeyCode::Char('w'), KeyModifiers::NONE) => self.display.next_group(),
+                    // Switch byte order of the words
+                    (KeyCode::Char('e'), KeyModifiers::NONE) => {
+                        self.display.endian = match self.display.endian {
+                            Endian::Little => Endian::Big,
+                            Endian::Big => Endian::Little,
+                        };
+                    }
+                    // Switch between raw digits and numbers
+                    (KeyCode::Char('f'), KeyModifiers::NONE) => self.display.next_word_format(),
+                    //   SHIFT + R -- switch address radix
+                    (KeyCode::Char('R'), KeyModifiers::SHIFT) => {
+                        self.display.address_radix = match self.display.address_radix {
+                            Radix::Hexadecimal => Radix::Decimal,
+                            _ => Radix::Hexadecimal,
+                        };
+                    }
+                    //   SHIFT + U -- switch case of hexadecimal digits
+                    (KeyCode::Char('U'), KeyModifiers::SHIFT) => {
+                        self.display.uppercase = !self.display.uppercase;
+                    }
+                    //   SHIFT + A -- switch between file offsets and virtual addresses
+                    (KeyCode::Char('A'), KeyModifiers::SHIFT) => {
+                        if self.buffer.address_map.is_empty() {
+                            self.message = Some(String::from(
+                                "no virtual address, set them with 'base' or 'map' commands",
+                            ));
+                        } else {
+                            self.buffer.toggle_address_space();
+                            self.scroll_to_cursor();
+                        }
+                    }
+                    //   SHIFT + F -- toggle follow mode
+                    (KeyCode::Char('F'), KeyModifiers::SHIFT) => {
+                        self.buffer.follow = !self.buffer.follow;
+                        if self.buffer.follow {
+                            self.move_cursor_to(self.data_size());
+                        }
+                    }
+                    //   SHIFT + Z -- open the compressed stream at the cursor
+                    (KeyCode::Char('Z'), KeyModifiers::SHIFT) => {
+                        if let Err(err) = self.decompress_at_cursor(None) {
+                            self.message = Some(err);
+                        }
+                    }
+                    //   SHIFT + X -- export the selection, the format being typed in the prompt
+                    (KeyCode::Char('X'), KeyModifiers::SHIFT) => {
+                        self.command = Some(String::from("export "));
+                    }
+                    // Open command prompt
+                    (KeyCode::Char(' '), KeyModifiers::NONE) => self.command = Some(String::new()),
                     _ => {}
                 }
             }
             Event::Mouse(mouse) => match mouse.kind {
-                MouseEventKind::ScrollDown => {
-                    if (self.scroll_pos + 1) * self.bytes_per_row < filesize {
-                        self.scroll_pos += 1;
+                MouseEventKind::ScrollDown => self.move_line_down(),
+                MouseEventKind::ScrollUp => self.move_line_up(),
+                // Click or drag on the minimap
+                MouseEventKind::Down(MouseButton::Left)
+                | MouseEventKind::Drag(MouseButton::Left)
+                    if self.buffer.minimap.contains(mouse.column, mouse.row) =>
+                {
+                    if let Some(ratio) = self.buffer.minimap.row_to_ratio(mouse.row) {
+                        self.scroll_to_ratio(ratio);
                     }
                 }
-                MouseEventKind::ScrollUp => {
-                    self.scroll_pos = self.scroll_pos.saturating_sub(1);
-                }
                 _ => {}
             },
             Event::Resize(width, height) => {
@@ -72,4 +231,206 @@ impl App {
             _ => {}
         }
     }
+
+    fn handle_command_key(&mut self, key: KeyEvent) {
+        let Some(command) = &mut self.command else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => self.command = None,
+            KeyCode::Enter => {
+                if let Some(command) = self.command.take() {
+                    self.execute_command(&command);
+                }
+            }
+            KeyCode::Backspace if command.is_empty() => self.command = None,
+            KeyCode::Backspace => {
+                command.pop();
+            }
+            KeyCode::Char(c) => command.push(c),
+            _ => {}
+        }
+    }
+
+    fn handle_mappings_key(&mut self, key: KeyEvent) {
+        let last = self.buffer.fileinfo.mappings.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('P') | KeyCode::Esc => self.show_mappings = false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.mappings_selected = std::cmp::min(self.mappings_selected + 1, last);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.mappings_selected = self.mappings_selected.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.mappings_selected =
+                    std::cmp::min(self.mappings_selected + self.page_height(), last);
+            }
+            KeyCode::PageUp => {
+                self.mappings_selected = self.mappings_selected.saturating_sub(self.page_height());
+            }
+            KeyCode::Enter => {
+                let mappings = &self.buffer.fileinfo.mappings;
+                if let Some(mapping) = mappings.get(self.mappings_selected)
+                    && mapping.len > 0
+                {
+                    self.move_cursor_to_offset(mapping.offset);
+                    self.show_mappings = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_members_key(&mut self, key: KeyEvent) {
+        let len = self.buffer.archive.as_ref().map_or(0, |a| a.members.len());
+        let last = len.saturating_sub(1);
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('L') | KeyCode::Esc => self.show_members = false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.members_selected = std::cmp::min(self.members_selected + 1, last);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.members_selected = self.members_selected.saturating_sub(1);
+            }
+            KeyCode::PageDown => {
+                self.members_selected =
+                    std::cmp::min(self.members_selected + self.page_height(), last);
+            }
+            KeyCode::PageUp => {
+                self.members_selected = self.members_selected.saturating_sub(self.page_height());
+            }
+            KeyCode::Enter if len > 0 => {
+                self.jump_to_member(self.members_selected);
+                self.show_members = false;
+            }
+            KeyCode::Char('o') if len > 0 => {
+                if let Err(err) = self.open_member(self.members_selected) {
+                    self.message = Some(err);
+                } else {
+                    self.show_members = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_bookmarks_key(&mut self, key: KeyEvent) {
+        let len = self.buffer.bookmarks.list.len();
+        let last = len.saturating_sub(1);
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('b') | KeyCode::Esc => self.show_bookmarks = false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.bookmarks_selected = std::cmp::min(self.bookmarks_selected + 1, last);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.bookmarks_selected = self.bookmarks_selected.saturating_sub(1);
+            }
+            KeyCode::Enter if len > 0 => {
+                let offset = self.buffer.bookmarks.list[self.bookmarks_selected].offset;
+                self.move_cursor_to_offset(offset);
+                self.show_bookmarks = false;
+            }
+            KeyCode::Char('d') if len > 0 => {
+                if let Err(err) = self.buffer.bookmarks.remove(self.bookmarks_selected) {
+                    self.message = Some(err);
+                }
+                // stays within the remaining bookmarks
+                self.bookmarks_selected = self.bookmarks_selected.min(last.saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_buffers_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('B') | KeyCode::Esc => self.show_buffers = false,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.buffers_selected =
+                    std::cmp::min(self.buffers_selected + 1, self.buffers.len() - 1);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.buffers_selected = self.buffers_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                self.switch_buffer(self.buffers_selected);
+                self.show_buffers = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_strings_key(&mut self, key: KeyEvent) {
+        self.buffer.strings.message = None;
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => self.buffer.strings.visible = false,
+            (KeyCode::Enter, _) => {
+                if let Some(offset) = self.buffer.strings.selected_string().map(|s| s.offset) {
+                    self.move_cursor_to_offset(offset);
+                    self.buffer.strings.visible = false;
+                }
+            }
+            (KeyCode::Down, _) => self.buffer.strings.select_next(1),
+            (KeyCode::Up, _) => self.buffer.strings.select_previous(1),
+            (KeyCode::PageDown, _) => self.buffer.strings.select_next(self.page_height()),
+            (KeyCode::PageUp, _) => self.buffer.strings.select_previous(self.page_height()),
+            (KeyCode::Tab, _) => {
+                self.buffer.strings.encoding = self.buffer.strings.encoding.next();
+                self.refresh_strings();
+            }
+            (KeyCode::Right, _) => {
+                self.buffer.strings.min_len += 1;
+                self.refresh_strings();
+            }
+            (KeyCode::Left, _) if self.buffer.strings.min_len > 1 => {
+                self.buffer.strings.min_len -= 1;
+                self.refresh_strings();
+            }
+            (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
+                let path = format!("{}.strings.txt", self.buffer.fileinfo.name);
+                self.buffer.strings.message = Some(match self.buffer.strings.export(&path) {
+                    Ok(count) => format!("{count} strings exported to '{path}'"),
+                    Err(err) => format!("failed to export strings: {err}"),
+                });
+            }
+            (KeyCode::Backspace, _) => {
+                self.buffer.strings.filter.pop();
+                self.buffer.strings.apply_filter();
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                self.buffer.strings.filter.push(c);
+                self.buffer.strings.apply_filter();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_fileinfo_key(&mut self, key: KeyEvent) {
+        self.message = None;
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('i') | KeyCode::Esc => self.show_fileinfo = false,
+            KeyCode::Char('j') | KeyCode::Down => self.buffer.hashes.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.buffer.hashes.select_previous(),
+            KeyCode::Enter => self.compute_hashes(vec![self.buffer.hashes.selected_algorithm()]),
+            KeyCode::Char('a') => self.compute_hashes(ALGORITHMS.to_vec()),
+            KeyCode::Char('y') => {
+                let algorithm = self.buffer.hashes.selected_algorithm();
+                self.message = Some(match self.buffer.hashes.selected_value() {
+                    Some(value) => match clipboard::copy_osc52(value) {
+                        Ok(()) => format!("{algorithm} copied to clipboard"),
+                        Err(err) => format!("failed to copy {algorithm}: {err}"),
+                    },
+                    None => format!("{algorithm} is not computed yet"),
+                });
+            }
+            _ => {}
+        }
+    }
 }
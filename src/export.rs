@@ -0,0 +1,368 @@
+use std::fmt::Write;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clap::ValueEnum;
+
+/// Default number of bytes per line of the arrays
+const ARRAY_WIDTH: usize = 12;
+/// Default number of characters per line of the encoded strings
+const STRING_WIDTH: usize = 76;
+/// Characters of base85, as in RFC 1924 (Python's `b85encode`, git patches)
+const BASE85_ALPHABET: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// Literal the exported bytes are written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// C/C++ `unsigned char[]`, like `xxd -i`
+    C,
+    /// Rust `[u8; N]`
+    Rust,
+    /// Rust byte string `b"..."`
+    RustBytes,
+    /// Python `bytes` literal
+    Python,
+    /// Go `[]byte`
+    Go,
+    /// Java `byte[]`
+    Java,
+    /// JavaScript `Uint8Array`
+    Js,
+    Base64,
+    Base85,
+    Hex,
+}
+
+impl ExportFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::C => "C",
+            ExportFormat::Rust => "Rust array",
+            ExportFormat::RustBytes => "Rust byte string",
+            ExportFormat::Python => "Python",
+            ExportFormat::Go => "Go",
+            ExportFormat::Java => "Java",
+            ExportFormat::Js => "JavaScript",
+            ExportFormat::Base64 => "base64",
+            ExportFormat::Base85 => "base85",
+            ExportFormat::Hex => "hex",
+        }
+    }
+
+    fn default_name(&self) -> &'static str {
+        match self {
+            // constants are upper case
+            ExportFormat::Rust | ExportFormat::RustBytes => "DATA",
+            _ => "data",
+        }
+    }
+}
+
+/// Layout of an export, inherited from cli flags
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Identifier of the variable, depending on the format if `None`
+    pub name: Option<String>,
+    /// Bytes per line of the arrays, or characters per line of the strings.
+    /// `Some(0)` writes everything on a single line.
+    pub width: Option<usize>,
+}
+
+/// Writes `bytes` as a literal of `format`, ending with a newline
+pub fn export(bytes: &[u8], format: ExportFormat, options: &ExportOptions) -> String {
+    let name = options
+        .name
+        .as_deref()
+        .unwrap_or_else(|| format.default_name());
+    let array_width = options.width.unwrap_or(ARRAY_WIDTH);
+    let string_width = options.width.unwrap_or(STRING_WIDTH);
+    let len = bytes.len();
+    let hex = |b: &u8| format!("0x{b:02x}");
+
+    match format {
+        ExportFormat::C => {
+            let items = block(array_lines(bytes, array_width, "  ", hex));
+            format!("unsigned char {name}[] = {{{items}}};\nunsigned int {name}_len = {len};\n")
+        }
+        ExportFormat::Rust => {
+            let items = block(array_lines(bytes, array_width, "    ", hex));
+            format!("pub const {name}: [u8; {len}] = [{items}];\n")
+        }
+        ExportFormat::RustBytes => {
+            // a `\` at the end of a line skips the newline and the indentation,
+            // as well as the spaces starting the next line
+            let lines = string_lines(&escape(bytes), string_width)
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| match line.strip_prefix(' ') {
+                    Some(rest) if i > 0 => format!("\\x20{rest}"),
+                    _ => line,
+                })
+                .collect::<Vec<_>>();
+            let string = lines.join("\\\n    ");
+            format!("pub const {name}: &[u8; {len}] = b\"{string}\";\n")
+        }
+        ExportFormat::Python => {
+            // adjacent literals are concatenated
+            let lines = string_lines(&escape(bytes), string_width);
+            match lines.as_slice() {
+                [] => format!("{name} = b\"\"\n"),
+                [line] => format!("{name} = b\"{line}\"\n"),
+                lines => {
+                    let lines = lines
+                        .iter()
+                        .map(|line| format!("    b\"{line}\"\n"))
+                        .collect::<String>();
+                    format!("{name} = (\n{lines})\n")
+                }
+            }
+        }
+        ExportFormat::Go => {
+            // gofmt indents with tabs and wants a trailing comma
+            let items = match array_lines(bytes, array_width, "\t", hex) {
+                items if items.is_empty() => items,
+                items => format!("\n{items},\n"),
+            };
+            format!("var {name} = []byte{{{items}}}\n")
+        }
+        ExportFormat::Java => {
+            // bytes are signed in Java
+            let items = block(array_lines(bytes, array_width, "    ", |&b| match b {
+                0x80.. => format!("(byte) 0x{b:02x}"),
+                _ => hex(&b),
+            }));
+            format!("byte[] {name} = {{{items}}};\n")
+        }
+        ExportFormat::Js => {
+            let items = block(array_lines(bytes, array_width, "  ", hex));
+            format!("const {name} = new Uint8Array([{items}]);\n")
+        }
+        ExportFormat::Base64 => wrapped(&STANDARD.encode(bytes), string_width),
+        ExportFormat::Base85 => wrapped(&base85(bytes), string_width),
+        ExportFormat::Hex => {
+            let digits = bytes.iter().fold(String::new(), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            });
+            wrapped(&digits, string_width)
+        }
+    }
+}
+
+/// Items of an array, `width` per line separated with commas
+fn array_lines(bytes: &[u8], width: usize, indent: &str, item: impl Fn(&u8) -> String) -> String {
+    let width = if width == 0 {
+        bytes.len().max(1)
+    } else {
+        width
+    };
+    bytes
+        .chunks(width)
+        .map(|chunk| {
+            let items = chunk.iter().map(&item).collect::<Vec<_>>();
+            format!("{indent}{}", items.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
+
+/// Items on their own lines, nothing if there is no item
+fn block(items: String) -> String {
+    if items.is_empty() {
+        items
+    } else {
+        format!("\n{items}\n")
+    }
+}
+
+/// Escapes the bytes for a byte string literal (Rust and Python)
+fn escape(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'"' => String::from("\\\""),
+            b'\\' => String::from("\\\\"),
+            b'\n' => String::from("\\n"),
+            b'\t' => String::from("\\t"),
+            b' '..=b'~' => String::from(b as char),
+            _ => format!("\\x{b:02x}"),
+        })
+        .collect()
+}
+
+/// Joins escaped bytes in lines of at most `width` characters, escape
+/// sequences being kept whole
+fn string_lines(escaped: &[String], width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for item in escaped {
+        if width != 0 && !line.is_empty() && line.len() + item.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        line.push_str(item);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Splits `s` in lines of `width` characters
+fn wrapped(s: &str, width: usize) -> String {
+    if width == 0 || s.is_empty() {
+        return format!("{s}\n");
+    }
+    s.as_bytes()
+        .chunks(width)
+        .map(|line| format!("{}\n", String::from_utf8_lossy(line)))
+        .collect()
+}
+
+/// Encodes `bytes` in base85, each 4 bytes being written as 5 characters,
+/// the last incomplete chunk being shortened
+fn base85(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(4) * 5);
+    for chunk in bytes.chunks(4) {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(word);
+        let mut digits = [0; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85_ALPHABET[(value % 85) as usize];
+            value /= 85;
+        }
+        for &digit in &digits[..chunk.len() + 1] {
+            encoded.push(digit as char);
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Quote, backslash, newline, tab, spaces and non-ASCII bytes
+    const BYTES: &[u8] = b"A \"q\\\n\t \x80\xff  tail";
+
+    fn export_width(bytes: &[u8], format: ExportFormat, width: usize) -> String {
+        let options = ExportOptions {
+            width: Some(width),
+            ..ExportOptions::default()
+        };
+        export(bytes, format, &options)
+    }
+
+    #[test]
+    fn rust_array() {
+        assert_eq!(
+            export_width(BYTES, ExportFormat::Rust, 8),
+            "\
+pub const DATA: [u8; 16] = [
+    0x41, 0x20, 0x22, 0x71, 0x5c, 0x0a, 0x09, 0x20,
+    0x80, 0xff, 0x20, 0x20, 0x74, 0x61, 0x69, 0x6c
+];
+"
+        );
+        assert_eq!(
+            export(&[], ExportFormat::Rust, &ExportOptions::default()),
+            "pub const DATA: [u8; 0] = [];\n"
+        );
+    }
+
+    #[test]
+    fn rust_byte_string() {
+        // the leading space of a continued line is escaped, as it is skipped
+        assert_eq!(
+            export_width(BYTES, ExportFormat::RustBytes, 8),
+            r#"pub const DATA: &[u8; 16] = b"A \"q\\\
+    \n\t \
+    \x80\xff\
+    \x20 tail";
+"#
+        );
+        let options = ExportOptions {
+            name: Some(String::from("EMPTY")),
+            width: None,
+        };
+        assert_eq!(
+            export(&[], ExportFormat::RustBytes, &options),
+            "pub const EMPTY: &[u8; 0] = b\"\";\n"
+        );
+    }
+
+    #[test]
+    fn python() {
+        assert_eq!(
+            export_width(BYTES, ExportFormat::Python, 8),
+            r#"data = (
+    b"A \"q\\"
+    b"\n\t "
+    b"\x80\xff"
+    b"  tail"
+)
+"#
+        );
+        assert_eq!(
+            export_width(b"ab", ExportFormat::Python, 8),
+            "data = b\"ab\"\n"
+        );
+    }
+
+    #[test]
+    fn go() {
+        // trailing comma on the last line, as gofmt writes it
+        assert_eq!(
+            export_width(BYTES, ExportFormat::Go, 8),
+            "\
+var data = []byte{
+\t0x41, 0x20, 0x22, 0x71, 0x5c, 0x0a, 0x09, 0x20,
+\t0x80, 0xff, 0x20, 0x20, 0x74, 0x61, 0x69, 0x6c,
+}
+"
+        );
+        assert_eq!(
+            export_width(&BYTES[..3], ExportFormat::Go, 0),
+            "var data = []byte{\n\t0x41, 0x20, 0x22,\n}\n"
+        );
+        assert_eq!(
+            export_width(&[], ExportFormat::Go, 8),
+            "var data = []byte{}\n"
+        );
+    }
+
+    #[test]
+    fn java() {
+        // bytes above 0x7f do not fit in a signed byte without a cast
+        assert_eq!(
+            export_width(BYTES, ExportFormat::Java, 8),
+            "\
+byte[] data = {
+    0x41, 0x20, 0x22, 0x71, 0x5c, 0x0a, 0x09, 0x20,
+    (byte) 0x80, (byte) 0xff, 0x20, 0x20, 0x74, 0x61, 0x69, 0x6c
+};
+"
+        );
+        assert_eq!(
+            export_width(&[], ExportFormat::Java, 8),
+            "byte[] data = {};\n"
+        );
+    }
+
+    #[test]
+    fn base85_as_rfc_1924() {
+        // as Python's `base64.b85encode`
+        assert_eq!(base85(b""), "");
+        assert_eq!(base85(b"h"), "Xa");
+        assert_eq!(base85(b"he"), "Xk`");
+        assert_eq!(base85(b"hel"), "Xk}~");
+        assert_eq!(base85(b"hell"), "Xk~0{");
+        assert_eq!(base85(b"hello world"), "Xk~0{Zy<MXa%^M");
+        assert_eq!(base85(&[0; 4]), "00000");
+        assert_eq!(base85(&[0xff; 4]), "|NsC0");
+        assert_eq!(
+            export_width(BYTES, ExportFormat::Base85, 10),
+            "K_DV=TnY&w\nfd3#MbYW?1\n"
+        );
+    }
+}
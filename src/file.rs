@@ -1,20 +1,133 @@
-use std::{fs, io, path::Path};
+use std::{
+    fs::{self, File},
+    io::{self, IsTerminal, Read},
+    path::Path,
+    sync::Arc,
+    thread,
+};
 
+use log::debug;
 use ratatui::text::{Line, Text};
-use sha2::{self, Digest};
+
+use crate::addressing::Segment;
+use crate::compression::Compression;
+#[cfg(unix)]
+use crate::device::{self, CHAR_DEVICE_MAX_LEN, DeviceKind};
+use crate::firmware::{self, ImageFormat};
+use crate::hash::CHUNK_SIZE;
+#[cfg(unix)]
+use crate::process;
+use crate::process::Mapping;
+use crate::worker::{WorkerMsg, WorkerSender};
+
+/// Path standing for the standard input, as in most command line tools
+pub const STDIN_PATH: &str = "-";
+const STDIN_NAME: &str = "<stdin>";
+
+/// State of an analysis run in the background
+#[derive(Debug, Clone)]
+pub enum Analysis<T> {
+    /// Still running, with its progress in percent
+    Running(usize),
+    Done(T),
+    Failed(String),
+}
+
+impl<T> Default for Analysis<T> {
+    fn default() -> Self {
+        Analysis::Running(0)
+    }
+}
+
+impl Analysis<String> {
+    fn to_line_value(&self) -> String {
+        match self {
+            Analysis::Running(percent) => format!("computing.. {percent}%"),
+            Analysis::Done(value) => value.clone(),
+            Analysis::Failed(err) => format!("unavailable ({err})"),
+        }
+    }
+}
+
+/// Where the content of a [`FileInfo`] was read from
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    #[default]
+    File,
+    Stdin,
+    BlockDevice {
+        sector_size: usize,
+    },
+    /// Only the beginning of the device is read, `truncated` if there was more
+    CharDevice {
+        truncated: bool,
+    },
+    /// Memory of a running process, see [`FileInfo::mappings`]
+    Process {
+        pid: u32,
+    },
+    /// Stream at `offset` of the file at `path`, decompressed
+    Decompressed {
+        compression: Compression,
+        offset: usize,
+    },
+    /// Member of the archive at `path`, extracted, its header being at
+    /// `offset`
+    Member {
+        offset: usize,
+    },
+}
 
 #[derive(Default, Debug)]
 pub struct FileInfo {
+    pub path: String,
     pub name: String,
-    pub sha256: String,
-    pub content: Vec<u8>,
+    pub sha256: Analysis<String>,
+    pub content: Arc<[u8]>,
     pub size: usize,
+    /// Progress in percent of the reading of a file in the background, the
+    /// content being empty until it is done
+    pub loading: Option<usize>,
+    /// Why the file could not be read in the background
+    pub read_error: Option<String>,
+    pub source: Source,
+    /// Whether `:save` may overwrite the opened file or device with the
+    /// displayed bytes, which is only allowed with `--allow-write`
+    pub writable: bool,
+    /// Memory mappings of a process, the content being their readable bytes
+    /// one after the other. Empty for anything else.
+    pub mappings: Vec<Mapping>,
+    /// Format the content was decoded from, firmware images holding their
+    /// data bytes only
+    pub format: ImageFormat,
+    /// Addresses of the data of a firmware image, the content being their
+    /// bytes one after the other. Empty for anything else.
+    pub segments: Vec<Segment>,
+    /// Start address of the program of a firmware image, if given
+    pub entry: Option<usize>,
+    /// Why a file looking like a firmware image is shown raw
+    pub firmware_error: Option<String>,
     #[cfg(target_os = "linux")]
-    pub filetype: String,
+    pub filetype: Analysis<String>,
 }
 
 impl FileInfo {
+    /// Opens `filepath` and reads its whole content
     pub fn new(filepath: &str) -> io::Result<Self> {
+        let mut fileinfo = FileInfo::open(filepath)?;
+        if fileinfo.loading.is_some() {
+            fileinfo.set_content(fs::read(filepath)?.into());
+        }
+        Ok(fileinfo)
+    }
+
+    /// Opens `filepath` like [`FileInfo::new`], except that the content of a
+    /// regular file is left to be read by [`FileInfo::spawn_loader`]
+    pub fn open(filepath: &str) -> io::Result<Self> {
+        if filepath == STDIN_PATH {
+            return FileInfo::from_stdin();
+        }
+
         let path = Path::new(filepath);
         if !path.exists() {
             return Err(io::Error::new(
@@ -23,6 +136,16 @@ impl FileInfo {
             ));
         }
 
+        #[cfg(unix)]
+        if let Some(pid) = process::pid_of_mem_path(filepath) {
+            return FileInfo::from_process(pid);
+        }
+
+        #[cfg(unix)]
+        if let Some(kind) = device::device_kind(path) {
+            return FileInfo::from_device(filepath, kind);
+        }
+
         if !path.is_file() {
             return Err(io::Error::new(
                 io::ErrorKind::IsADirectory,
@@ -30,24 +153,242 @@ impl FileInfo {
             ));
         }
 
+        // fails right away if the file cannot be read
+        File::open(path)?;
         let strict_filename = path.file_name().unwrap().to_os_string();
-        let content = fs::read(filepath)?;
-        let sha256 = calc_sha256(&content);
-        #[cfg(target_os = "linux")]
-        let filetype = get_filetype(filepath)?;
 
         Ok(FileInfo {
+            path: filepath.to_string(),
             name: strict_filename.into_string().unwrap_or_default(),
+            loading: Some(0),
+            ..FileInfo::default()
+        })
+    }
+
+    /// Reads the file in chunks on a background thread, reporting the
+    /// progress, so that a large file does not hold up the interface
+    pub fn spawn_loader(&self, tx: WorkerSender) {
+        let path = self.path.clone();
+        thread::spawn(move || {
+            let content = read_in_chunks(&path, |percent| {
+                tx.send(WorkerMsg::FileLoading(percent)).is_ok()
+            });
+            let content = match content {
+                Ok(Some(content)) => Ok(content.into()),
+                // application is gone
+                Ok(None) => return,
+                Err(err) => Err(err.to_string()),
+            };
+            debug!("'{path}' read in the background");
+            let _ = tx.send(WorkerMsg::FileLoaded(content));
+        });
+    }
+
+    /// Installs the content read by [`FileInfo::spawn_loader`]
+    pub fn set_content(&mut self, content: Arc<[u8]>) {
+        self.size = content.len();
+        self.content = content;
+        self.loading = None;
+        self.decode_firmware();
+    }
+
+    /// Reads the whole standard input, so that a pipe can be navigated like a
+    /// regular file
+    pub fn from_stdin() -> io::Result<Self> {
+        let mut stdin = io::stdin().lock();
+        if stdin.is_terminal() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "standard input is a terminal, pipe or redirect some data into it",
+            ));
+        }
+
+        let mut content = vec![];
+        stdin.read_to_end(&mut content)?;
+        debug!("{} bytes read from standard input", content.len());
+        let content: Arc<[u8]> = content.into();
+
+        let mut fileinfo = FileInfo {
+            path: STDIN_PATH.to_string(),
+            name: STDIN_NAME.to_string(),
             size: content.len(),
             content,
-            sha256,
-            #[cfg(target_os = "linux")]
-            filetype,
+            source: Source::Stdin,
+            ..FileInfo::default()
+        };
+        fileinfo.decode_firmware();
+        Ok(fileinfo)
+    }
+
+    /// Replaces the text of an Intel HEX, S-record or TI-TXT image with its
+    /// data, the raw text being kept if it cannot be decoded
+    fn decode_firmware(&mut self) {
+        let format = ImageFormat::detect(&self.content);
+        if format == ImageFormat::Raw {
+            return;
+        }
+        let text = String::from_utf8_lossy(&self.content);
+        match firmware::parse(&text, format) {
+            Ok(image) => {
+                debug!(
+                    "{} image decoded: {} bytes in {} ranges",
+                    format.name(),
+                    image.content.len(),
+                    image.segments.len()
+                );
+                self.content = image.content.into();
+                self.size = self.content.len();
+                self.format = format;
+                self.segments = image.segments;
+                self.entry = image.entry;
+            }
+            Err(err) => self.firmware_error = Some(format!("{}: {err}", format.name())),
+        }
+    }
+
+    /// Reads a block or character device, see [`device`]
+    #[cfg(unix)]
+    fn from_device(filepath: &str, kind: DeviceKind) -> io::Result<Self> {
+        let path = Path::new(filepath);
+        let mut file = device::open(path)?;
+        let (content, source) = match kind {
+            DeviceKind::Block => {
+                let sector_size = device::sector_size(path);
+                let content = device::read_block_device(&mut file, sector_size)?;
+                (content, Source::BlockDevice { sector_size })
+            }
+            DeviceKind::Char => {
+                let (content, truncated) = device::read_char_device(&mut file)?;
+                (content, Source::CharDevice { truncated })
+            }
+        };
+        let content: Arc<[u8]> = content.into();
+        debug!("{:?} '{filepath}' opened", source);
+
+        Ok(FileInfo {
+            path: filepath.to_string(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            size: content.len(),
+            content,
+            source,
+            ..FileInfo::default()
         })
     }
 
+    /// Reads the memory of the process `pid`, see [`process`]
+    #[cfg(unix)]
+    fn from_process(pid: u32) -> io::Result<Self> {
+        let (content, mappings) = process::read_memory(pid)?;
+        let content: Arc<[u8]> = content.into();
+
+        Ok(FileInfo {
+            path: process::mem_path(pid),
+            name: format!("pid {pid} ({})", process::process_name(pid)),
+            size: content.len(),
+            content,
+            source: Source::Process { pid },
+            mappings,
+            ..FileInfo::default()
+        })
+    }
+
+    /// Whether `path` names the file the content was read from
+    pub fn is_at(&self, path: &str) -> bool {
+        match (fs::canonicalize(path), fs::canonicalize(&self.path)) {
+            (Ok(path), Ok(own)) => path == own,
+            _ => false,
+        }
+    }
+
+    /// Index in `mappings` of the mapping containing `offset`
+    pub fn mapping_at(&self, offset: usize) -> Option<usize> {
+        let idx = self
+            .mappings
+            .partition_point(|mapping| mapping.offset + mapping.len <= offset);
+        self.mappings
+            .get(idx)
+            .filter(|mapping| mapping.address_of(offset).is_some())
+            .map(|_| idx)
+    }
+
+    /// Whether the byte at `offset` could be read, only pages of a process
+    /// may not
+    pub fn is_readable(&self, offset: usize) -> bool {
+        match self.mapping_at(offset) {
+            Some(idx) => !self.mappings[idx]
+                .unreadable
+                .iter()
+                .any(|range| range.contains(&offset)),
+            None => true,
+        }
+    }
+
+    /// Warning displayed above the panes for files needing extra care
+    pub fn banner(&self) -> Option<String> {
+        let access = if self.writable {
+            "read-only, :save may overwrite it (--allow-write)"
+        } else {
+            "read-only"
+        };
+        if let Some(percent) = self.loading {
+            return Some(format!("Reading {}.. {percent}%", self.path));
+        }
+        if let Some(err) = &self.read_error {
+            return Some(format!("Failed to read {}: {err}", self.path));
+        }
+        if let Some(err) = &self.firmware_error {
+            return Some(format!(
+                "Not decoded as a firmware image ({err}), raw text shown"
+            ));
+        }
+        match self.source {
+            Source::BlockDevice { sector_size } => Some(format!(
+                "Block device {}, {sector_size} bytes sectors, opened {access}",
+                self.path
+            )),
+            Source::CharDevice { truncated } => Some(format!(
+                "Character device {}, opened {access}{}",
+                self.path,
+                if truncated {
+                    format!(", only the first {} bytes are shown", CHAR_DEVICE_MAX_LEN)
+                } else {
+                    String::new()
+                }
+            )),
+            Source::File | Source::Stdin | Source::Member { .. } => {
+                Compression::detect(&self.content)
+                    .filter(|compression| *compression != Compression::Zlib)
+                    .map(|compression| {
+                        format!("{compression} compressed data, press Z to open it decompressed")
+                    })
+            }
+            Source::Process { .. } | Source::Decompressed { .. } => None,
+        }
+    }
+
+    /// Detects the file type displayed in the file details on a background
+    /// thread, so that the content can be displayed right away
+    #[cfg(target_os = "linux")]
+    pub fn spawn_filetype(&self, tx: WorkerSender) {
+        let path = self.path.clone();
+        // the standard input was consumed and `file` does not look into
+        // devices, it reads a copy of the content instead
+        let content = (self.source != Source::File).then(|| self.content.clone());
+        thread::spawn(move || {
+            let filetype = match content {
+                Some(content) => get_filetype_of_content(&content),
+                None => get_filetype(&path),
+            }
+            .map_err(|err| err.to_string());
+            let _ = tx.send(WorkerMsg::FileType(filetype));
+        });
+    }
+
     pub fn to_text(&self) -> Text<'_> {
-        Text::from(vec![
+        let mut text = Text::from(vec![
             Line::from(vec!["name:   ".into(), self.name.as_str().into()]),
             Line::from(vec![
                 "size:   ".into(),
@@ -55,18 +396,71 @@ impl FileInfo {
                 " bytes".into(),
             ]),
             #[cfg(target_os = "linux")]
-            Line::from(vec!["type:   ".into(), self.filetype.as_str().into()]),
-            Line::from(vec!["sha256: ".into(), self.sha256.as_str().into()]),
-        ])
+            Line::from(vec![
+                "type:   ".into(),
+                self.filetype.to_line_value().into(),
+            ]),
+            Line::from(vec!["sha256: ".into(), self.sha256.to_line_value().into()]),
+        ]);
+        if self.format != ImageFormat::Raw {
+            let entry = self
+                .entry
+                .map(|entry| format!(", entry {entry:#x}"))
+                .unwrap_or_default();
+            text.push_line(format!(
+                "format: {}, {} ranges{entry}",
+                self.format.name(),
+                self.segments.len()
+            ));
+        }
+        match self.source {
+            Source::File => {}
+            Source::Stdin => text.push_line("source: standard input (buffered in memory)"),
+            Source::BlockDevice { .. } => text.push_line("source: block device"),
+            Source::CharDevice { .. } => text.push_line("source: character device"),
+            Source::Process { pid } => {
+                let readable = self.mappings.iter().filter(|m| m.len > 0).count();
+                text.push_line(format!(
+                    "source: memory of process {pid}, {readable}/{} mappings read",
+                    self.mappings.len()
+                ));
+            }
+            Source::Decompressed {
+                compression,
+                offset,
+            } => text.push_line(format!(
+                "source: {compression} stream at {offset:#x} of {}",
+                self.path
+            )),
+            Source::Member { offset } => text.push_line(format!(
+                "source: member at {offset:#x} of the archive {}",
+                self.path
+            )),
+        }
+
+        text
     }
 }
 
-fn calc_sha256(content: &[u8]) -> String {
-    let mut hasher = sha2::Sha256::new();
-    hasher.update(content);
-    let hash = hasher.finalize();
-
-    format!("{hash:x}")
+/// Reads the file at `path`, calling `progress` with the percentage done
+/// after each chunk. Stops early if `progress` returns false.
+fn read_in_chunks(path: &str, progress: impl Fn(usize) -> bool) -> io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len() as usize;
+    let mut content = Vec::with_capacity(size);
+    loop {
+        let read = (&mut file)
+            .take(CHUNK_SIZE as u64)
+            .read_to_end(&mut content)?;
+        if read == 0 {
+            return Ok(Some(content));
+        }
+        // the file may grow while it is read
+        let percent = std::cmp::min((content.len() * 100).checked_div(size).unwrap_or(100), 100);
+        if !progress(percent) {
+            return Ok(None);
+        }
+    }
 }
 
 #[cfg(target_os = "linux")]
@@ -82,3 +476,23 @@ fn get_filetype(filename: &str) -> io::Result<String> {
 
     Ok(filetype.to_string())
 }
+
+/// Runs the `file` command over `content` given on its standard input
+#[cfg(target_os = "linux")]
+fn get_filetype_of_content(content: &[u8]) -> io::Result<String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new("file")
+        .args(["-b", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // `file` only reads the beginning of its input and may close it early
+        let _ = stdin.write_all(&content[..std::cmp::min(content.len(), CHUNK_SIZE)]);
+    }
+    let output = child.wait_with_output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,567 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::addressing::Segment;
+
+/// Data bytes per record written by [`write`]
+const RECORD_LEN: usize = 16;
+/// Byte filling the gaps between the ranges of a raw image, as erased flash
+const GAP_FILL: u8 = 0xff;
+/// Largest total of the gaps filled in a raw image, ranges as far apart as
+/// the mappings of a process not fitting in memory
+const MAX_GAP_FILL: usize = 1 << 30;
+
+/// Format of the bytes of an opened file, firmware images being decoded into
+/// their data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImageFormat {
+    /// Bytes of the file as is
+    #[default]
+    Raw,
+    /// Intel HEX, `:LLAAAATT...CC` records
+    #[value(name = "ihex")]
+    IntelHex,
+    /// Motorola S-record, `STLLAAAA...CC` records
+    #[value(name = "srec")]
+    SRecord,
+    /// Texas Instruments text, `@ADDR` followed by bytes and ending with `q`
+    TiTxt,
+}
+
+impl ImageFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Raw => "raw",
+            ImageFormat::IntelHex => "Intel HEX",
+            ImageFormat::SRecord => "Motorola S-record",
+            ImageFormat::TiTxt => "TI-TXT",
+        }
+    }
+
+    /// Guesses the format of a firmware image from its first line, `Raw`
+    /// for anything else
+    pub fn detect(content: &[u8]) -> Self {
+        let Some(text) = std::str::from_utf8(content).ok().filter(|s| s.is_ascii()) else {
+            return ImageFormat::Raw;
+        };
+        let Some(first) = text.lines().map(str::trim).find(|line| !line.is_empty()) else {
+            return ImageFormat::Raw;
+        };
+        let hex_after = |prefix_len: usize| {
+            first.len() > prefix_len && first[prefix_len..].bytes().all(|b| b.is_ascii_hexdigit())
+        };
+        match first.as_bytes() {
+            [b':', ..] if hex_after(1) => ImageFormat::IntelHex,
+            [b'S', b'0'..=b'9', ..] if hex_after(2) => ImageFormat::SRecord,
+            [b'@', ..] if hex_after(1) => ImageFormat::TiTxt,
+            _ => ImageFormat::Raw,
+        }
+    }
+}
+
+/// Data of a firmware image, its ranges being stored one after the other
+#[derive(Debug, Default)]
+pub struct Image {
+    pub content: Vec<u8>,
+    /// Sorted by address, contiguous records being merged
+    pub segments: Vec<Segment>,
+    /// Start address of the program, if given
+    pub entry: Option<usize>,
+}
+
+impl Image {
+    /// Gathers the records, given as their address and data
+    fn from_records(
+        mut records: Vec<(usize, Vec<u8>)>,
+        entry: Option<usize>,
+    ) -> Result<Self, String> {
+        records.sort_by_key(|(address, _)| *address);
+        let mut image = Image {
+            entry,
+            ..Image::default()
+        };
+        for (address, data) in records.into_iter().filter(|(_, data)| !data.is_empty()) {
+            match image.segments.last_mut() {
+                Some(last) if address < last.address + last.len => {
+                    return Err(format!("records overlap at address {address:#x}"));
+                }
+                Some(last) if address == last.address + last.len => last.len += data.len(),
+                _ => image.segments.push(Segment {
+                    offset: image.content.len(),
+                    len: data.len(),
+                    address,
+                }),
+            }
+            image.content.extend_from_slice(&data);
+        }
+        Ok(image)
+    }
+}
+
+/// Decodes a firmware image, checking the checksums of its records. Errors
+/// are reported with their line number.
+pub fn parse(text: &str, format: ImageFormat) -> Result<Image, String> {
+    match format {
+        ImageFormat::Raw => Err(String::from("raw bytes are not a firmware image")),
+        ImageFormat::IntelHex => parse_ihex(text),
+        ImageFormat::SRecord => parse_srec(text),
+        ImageFormat::TiTxt => parse_ti_txt(text),
+    }
+}
+
+fn parse_ihex(text: &str) -> Result<Image, String> {
+    let mut records = vec![];
+    let mut base = 0;
+    let mut entry = None;
+    for (i, line) in text.lines().enumerate() {
+        let err = |msg: String| format!("line {}: {msg}", i + 1);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let bytes = line
+            .strip_prefix(':')
+            .and_then(parse_hex)
+            .ok_or_else(|| err(String::from("expected ':' followed by hexadecimal bytes")))?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(err(String::from("the length does not match the record")));
+        }
+        if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+            return Err(err(String::from("wrong checksum")));
+        }
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as usize;
+        let data = &bytes[4..bytes.len() - 1];
+        let value = || data.iter().fold(0, |value, &b| (value << 8) | b as usize);
+        match (bytes[3], data.len()) {
+            (0x00, _) => records.push((base + offset, data.to_vec())),
+            (0x01, 0) => break,
+            // segment base of the 8086, in paragraphs of 16 bytes
+            (0x02, 2) => base = value() << 4,
+            // CS:IP of the 8086
+            (0x03, 4) => entry = Some(((value() >> 16) << 4) + (value() & 0xffff)),
+            (0x04, 2) => base = value() << 16,
+            (0x05, 4) => entry = Some(value()),
+            (kind @ 0x01..=0x05, _) => {
+                return Err(err(format!("wrong length for a record of type {kind:02X}")));
+            }
+            (kind, _) => return Err(err(format!("unknown record type {kind:02X}"))),
+        }
+    }
+    Image::from_records(records, entry)
+}
+
+fn parse_srec(text: &str) -> Result<Image, String> {
+    let mut records = vec![];
+    let mut entry = None;
+    for (i, line) in text.lines().enumerate() {
+        let err = |msg: String| format!("line {}: {msg}", i + 1);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (kind, bytes) = line
+            .strip_prefix('S')
+            .and_then(|line| line.split_at_checked(1))
+            .and_then(|(kind, hex)| Some((kind.parse::<u8>().ok()?, parse_hex(hex)?)))
+            .ok_or_else(|| err(String::from("expected 'S', the type and hexadecimal bytes")))?;
+        if bytes.len() < 2 || bytes.len() != bytes[0] as usize + 1 {
+            return Err(err(String::from("the length does not match the record")));
+        }
+        if bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0xff {
+            return Err(err(String::from("wrong checksum")));
+        }
+        let address_len = match kind {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err(err(format!("unknown record type S{kind}"))),
+        };
+        if bytes.len() < address_len + 2 {
+            return Err(err(format!("record S{kind} is too short")));
+        }
+        let address = bytes[1..=address_len]
+            .iter()
+            .fold(0, |value, &b| (value << 8) | b as usize);
+        let data = &bytes[address_len + 1..bytes.len() - 1];
+        match kind {
+            1..=3 => records.push((address, data.to_vec())),
+            7..=9 => entry = Some(address),
+            // header and count of records
+            _ => {}
+        }
+    }
+    Image::from_records(records, entry)
+}
+
+fn parse_ti_txt(text: &str) -> Result<Image, String> {
+    let mut records = vec![];
+    let mut current: Option<(usize, Vec<u8>)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let err = |msg: String| format!("line {}: {msg}", i + 1);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("q") {
+            break;
+        }
+        if let Some(address) = line.strip_prefix('@') {
+            let address = usize::from_str_radix(address, 16)
+                .map_err(|_| err(format!("'{address}' is not a valid address")))?;
+            records.extend(current.replace((address, vec![])));
+            continue;
+        }
+        let (_, data) = current
+            .as_mut()
+            .ok_or_else(|| err(String::from("expected '@' followed by an address")))?;
+        for byte in line.split_whitespace() {
+            let byte = u8::from_str_radix(byte, 16)
+                .ok()
+                .filter(|_| byte.len() == 2)
+                .ok_or_else(|| err(format!("'{byte}' is not a valid byte")))?;
+            data.push(byte);
+        }
+    }
+    records.extend(current);
+    Image::from_records(records, None)
+}
+
+fn parse_hex(digits: &str) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Encodes the bytes of `content` mapped by `segments` in `format`. The raw
+/// format holds the bytes from the lowest to the highest address, gaps being
+/// filled with `0xff`.
+pub fn write(
+    content: &[u8],
+    segments: &[Segment],
+    entry: Option<usize>,
+    format: ImageFormat,
+) -> Result<Vec<u8>, String> {
+    // ranges of the content, sorted by address
+    let mut segments = segments
+        .iter()
+        .filter(|segment| segment.offset < content.len())
+        .map(|segment| Segment {
+            len: std::cmp::min(segment.len, content.len() - segment.offset),
+            ..*segment
+        })
+        .collect::<Vec<_>>();
+    segments.sort_by_key(|segment| segment.address);
+    let data = |segment: &Segment| &content[segment.offset..segment.offset + segment.len];
+    let end = segments
+        .iter()
+        .map(|segment| segment.address.saturating_add(segment.len))
+        .max()
+        .unwrap_or(0);
+
+    Ok(match format {
+        ImageFormat::Raw => {
+            let start = segments.first().map_or(0, |segment| segment.address);
+            let filled = segments.iter().map(|segment| segment.len).sum::<usize>();
+            if (end - start).saturating_sub(filled) > MAX_GAP_FILL {
+                return Err(format!(
+                    "the ranges span {:#x} bytes, too far apart for a raw image",
+                    end - start
+                ));
+            }
+            let mut raw = vec![GAP_FILL; end - start];
+            for segment in &segments {
+                let offset = segment.address - start;
+                raw[offset..offset + segment.len].copy_from_slice(data(segment));
+            }
+            raw
+        }
+        ImageFormat::IntelHex => {
+            if end as u64 > 1 << 32 {
+                return Err(format!("address {:#x} does not fit in Intel HEX", end - 1));
+            }
+            let mut text = String::new();
+            let mut base = 0;
+            for segment in &segments {
+                for (address, chunk) in chunks(segment.address, data(segment)) {
+                    if address >> 16 != base {
+                        base = address >> 16;
+                        ihex_record(&mut text, 0, 0x04, &(base as u16).to_be_bytes());
+                    }
+                    ihex_record(&mut text, address as u16, 0x00, chunk);
+                }
+            }
+            if let Some(entry) = entry {
+                ihex_record(&mut text, 0, 0x05, &(entry as u32).to_be_bytes());
+            }
+            ihex_record(&mut text, 0, 0x01, &[]);
+            text.into_bytes()
+        }
+        ImageFormat::SRecord => {
+            let last = std::cmp::max(end, entry.unwrap_or(0) + 1) - 1;
+            let address_len = match last {
+                0..=0xffff => 2,
+                0x1_0000..=0xff_ffff => 3,
+                0x100_0000..=0xffff_ffff => 4,
+                _ => return Err(format!("address {last:#x} does not fit in S-record")),
+            };
+            let mut text = String::new();
+            let mut count = 0;
+            srec_record(&mut text, 0, 0, 2, &[]);
+            for segment in &segments {
+                for (address, chunk) in chunks(segment.address, data(segment)) {
+                    srec_record(&mut text, address_len - 1, address, address_len, chunk);
+                    count += 1;
+                }
+            }
+            if count <= 0xffff {
+                srec_record(&mut text, 5, count, 2, &[]);
+            } else if count <= 0xff_ffff {
+                srec_record(&mut text, 6, count, 3, &[]);
+            }
+            srec_record(
+                &mut text,
+                11 - address_len,
+                entry.unwrap_or(0),
+                address_len,
+                &[],
+            );
+            text.into_bytes()
+        }
+        ImageFormat::TiTxt => {
+            let mut text = String::new();
+            for segment in &segments {
+                let _ = writeln!(text, "@{:04X}", segment.address);
+                for line in data(segment).chunks(RECORD_LEN) {
+                    let bytes = line.iter().map(|b| format!("{b:02X}")).collect::<Vec<_>>();
+                    let _ = writeln!(text, "{}", bytes.join(" "));
+                }
+            }
+            text.push_str("q\n");
+            text.into_bytes()
+        }
+    })
+}
+
+/// Splits `data` in records, which do not cross 64 KiB boundaries so that
+/// the Intel HEX offsets do not wrap
+fn chunks(mut address: usize, mut data: &[u8]) -> Vec<(usize, &[u8])> {
+    let mut chunks = vec![];
+    while !data.is_empty() {
+        let len = RECORD_LEN
+            .min(data.len())
+            .min(0x1_0000 - (address & 0xffff));
+        let (chunk, rest) = data.split_at(len);
+        chunks.push((address, chunk));
+        address += len;
+        data = rest;
+    }
+    chunks
+}
+
+fn ihex_record(text: &mut String, offset: u16, kind: u8, data: &[u8]) {
+    let [high, low] = offset.to_be_bytes();
+    let mut bytes = vec![data.len() as u8, high, low, kind];
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    bytes.push(sum.wrapping_neg());
+    let _ = writeln!(text, ":{}", hex_string(&bytes));
+}
+
+fn srec_record(text: &mut String, kind: usize, address: usize, address_len: usize, data: &[u8]) {
+    let mut bytes = vec![(address_len + data.len() + 1) as u8];
+    bytes.extend_from_slice(&(address as u32).to_be_bytes()[4 - address_len..]);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+    bytes.push(!sum);
+    let _ = writeln!(text, "S{kind}{}", hex_string(&bytes));
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02X}");
+        s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two ranges apart, the second one crossing a 64 KiB boundary
+    fn image() -> Image {
+        Image {
+            content: (0..40).collect(),
+            segments: vec![
+                Segment {
+                    offset: 0,
+                    len: 8,
+                    address: 0x100,
+                },
+                Segment {
+                    offset: 8,
+                    len: 32,
+                    address: 0x1_fff0,
+                },
+            ],
+            entry: Some(0x1_0000),
+        }
+    }
+
+    fn round_trip(format: ImageFormat) -> Image {
+        let image = image();
+        let bytes = write(&image.content, &image.segments, image.entry, format).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        assert_eq!(ImageFormat::detect(text.as_bytes()), format);
+        parse(&text, format).unwrap()
+    }
+
+    #[test]
+    fn ihex_round_trip() {
+        let decoded = round_trip(ImageFormat::IntelHex);
+        assert_eq!(decoded.content, image().content);
+        assert_eq!(decoded.segments, image().segments);
+        assert_eq!(decoded.entry, Some(0x1_0000));
+    }
+
+    #[test]
+    fn srec_round_trip() {
+        let decoded = round_trip(ImageFormat::SRecord);
+        assert_eq!(decoded.content, image().content);
+        assert_eq!(decoded.segments, image().segments);
+        assert_eq!(decoded.entry, Some(0x1_0000));
+    }
+
+    #[test]
+    fn ti_txt_round_trip() {
+        let decoded = round_trip(ImageFormat::TiTxt);
+        assert_eq!(decoded.content, image().content);
+        assert_eq!(decoded.segments, image().segments);
+        // TI-TXT has no entry point
+        assert_eq!(decoded.entry, None);
+    }
+
+    #[test]
+    fn raw_fills_gaps() {
+        let segments = [
+            Segment {
+                offset: 0,
+                len: 2,
+                address: 0x10,
+            },
+            Segment {
+                offset: 2,
+                len: 1,
+                address: 0x14,
+            },
+        ];
+        let raw = write(&[1, 2, 3], &segments, None, ImageFormat::Raw).unwrap();
+        assert_eq!(raw, [1, 2, 0xff, 0xff, 3]);
+    }
+
+    #[test]
+    fn raw_refuses_far_apart_ranges() {
+        // as the mappings of a process
+        let segments = [
+            Segment {
+                offset: 0,
+                len: 2,
+                address: 0x5555_5555_0000,
+            },
+            Segment {
+                offset: 2,
+                len: 1,
+                address: 0x7ffc_0000_0000,
+            },
+        ];
+        assert_eq!(
+            write(&[1, 2, 3], &segments, None, ImageFormat::Raw).unwrap_err(),
+            "the ranges span 0x2aa6aaab0001 bytes, too far apart for a raw image"
+        );
+    }
+
+    #[test]
+    fn ihex_extended_addresses() {
+        // extended segment address 0x1000, i.e. base 0x10000, then extended
+        // linear address 0x0002, i.e. base 0x20000
+        let text = ":020000021000EC\n:0100000055AA\n:020000040002F8\n:010010006689\n:00000001FF\n";
+        let image = parse_ihex(text).unwrap();
+        assert_eq!(image.content, [0x55, 0x66]);
+        assert_eq!(
+            image.segments,
+            [
+                Segment {
+                    offset: 0,
+                    len: 1,
+                    address: 0x1_0000,
+                },
+                Segment {
+                    offset: 1,
+                    len: 1,
+                    address: 0x2_0010,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn srec_24_bit_addresses() {
+        let text = "S00600004844521B\nS205123456015D\nS8041234565F\n";
+        let image = parse_srec(text).unwrap();
+        assert_eq!(image.content, [0x01]);
+        assert_eq!(image.segments[0].address, 0x12_3456);
+        assert_eq!(image.entry, Some(0x12_3456));
+    }
+
+    #[test]
+    fn ti_txt_gaps() {
+        let image = parse_ti_txt("@0100\n01 02\n@0200\n03\nq\n").unwrap();
+        assert_eq!(image.content, [1, 2, 3]);
+        let addresses = image
+            .segments
+            .iter()
+            .map(|segment| (segment.address, segment.len))
+            .collect::<Vec<_>>();
+        assert_eq!(addresses, [(0x100, 2), (0x200, 1)]);
+    }
+
+    #[test]
+    fn checksum_errors() {
+        assert_eq!(
+            parse_ihex(":0100000055AA\n:0100000055AB\n").unwrap_err(),
+            "line 2: wrong checksum"
+        );
+        assert_eq!(
+            parse_srec("S00600004844521B\nS1040000010A\n").unwrap_err(),
+            "line 2: wrong checksum"
+        );
+    }
+
+    #[test]
+    fn malformed_records() {
+        assert_eq!(
+            parse_ihex(":0200000055A9\n").unwrap_err(),
+            "line 1: the length does not match the record"
+        );
+        assert_eq!(
+            parse_ihex(":0100000655A4\n").unwrap_err(),
+            "line 1: unknown record type 06"
+        );
+        assert_eq!(
+            parse_ti_txt("01 02\n").unwrap_err(),
+            "line 1: expected '@' followed by an address"
+        );
+        assert_eq!(
+            parse_ti_txt("@0000\n123\n").unwrap_err(),
+            "line 2: '123' is not a valid byte"
+        );
+        assert_eq!(
+            parse_ti_txt("@0000\n01 02\n@0001\n03\n").unwrap_err(),
+            "records overlap at address 0x1"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+
+use crc::{CRC_32_BZIP2, CRC_32_ISCSI, CRC_32_ISO_HDLC, CRC_32_MPEG_2, Crc};
+use fuzzyhash::FuzzyHash;
+use log::debug;
+use ratatui::{
+    style::{Style, Stylize},
+    text::Line,
+};
+use sha2::Digest;
+use tlsh2::TlshDefaultBuilder;
+
+use crate::worker::{WorkerMsg, WorkerSender};
+
+/// Amount of bytes hashed, or read, between two progress reports
+pub const CHUNK_SIZE: usize = 1 << 20;
+
+static CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+static CRC32C: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+static CRC32_BZIP2: Crc<u32> = Crc::<u32>::new(&CRC_32_BZIP2);
+static CRC32_MPEG2: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Blake3,
+    Crc32,
+    Crc32c,
+    Crc32Bzip2,
+    Crc32Mpeg2,
+    Adler32,
+    Ssdeep,
+    Tlsh,
+}
+
+pub const ALGORITHMS: [HashAlgorithm; 12] = [
+    HashAlgorithm::Md5,
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha512,
+    HashAlgorithm::Blake3,
+    HashAlgorithm::Crc32,
+    HashAlgorithm::Crc32c,
+    HashAlgorithm::Crc32Bzip2,
+    HashAlgorithm::Crc32Mpeg2,
+    HashAlgorithm::Adler32,
+    HashAlgorithm::Ssdeep,
+    HashAlgorithm::Tlsh,
+];
+
+impl HashAlgorithm {
+    /// Position of the algorithm in [`ALGORITHMS`], where its value is shown
+    fn index(self) -> usize {
+        ALGORITHMS
+            .iter()
+            .position(|&algorithm| algorithm == self)
+            .expect("every algorithm is listed in ALGORITHMS")
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Sha512 => "sha512",
+            HashAlgorithm::Blake3 => "blake3",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Crc32c => "crc32c",
+            HashAlgorithm::Crc32Bzip2 => "crc32/bzip2",
+            HashAlgorithm::Crc32Mpeg2 => "crc32/mpeg2",
+            HashAlgorithm::Adler32 => "adler32",
+            HashAlgorithm::Ssdeep => "ssdeep",
+            HashAlgorithm::Tlsh => "tlsh",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Incremental state of a hash computation
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Sha512(sha2::Sha512),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc::Digest<'static, u32>),
+    Adler32 { a: u32, b: u32 },
+    Ssdeep(Box<FuzzyHash>),
+    Tlsh(Box<TlshDefaultBuilder>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(sha2::Sha512::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Crc32 => Hasher::Crc32(CRC32.digest()),
+            HashAlgorithm::Crc32c => Hasher::Crc32(CRC32C.digest()),
+            HashAlgorithm::Crc32Bzip2 => Hasher::Crc32(CRC32_BZIP2.digest()),
+            HashAlgorithm::Crc32Mpeg2 => Hasher::Crc32(CRC32_MPEG2.digest()),
+            HashAlgorithm::Adler32 => Hasher::Adler32 { a: 1, b: 0 },
+            HashAlgorithm::Ssdeep => Hasher::Ssdeep(Box::default()),
+            HashAlgorithm::Tlsh => Hasher::Tlsh(Box::new(TlshDefaultBuilder::new())),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(bytes),
+            Hasher::Sha1(h) => h.update(bytes),
+            Hasher::Sha256(h) => h.update(bytes),
+            Hasher::Sha512(h) => h.update(bytes),
+            Hasher::Blake3(h) => {
+                h.update(bytes);
+            }
+            Hasher::Crc32(h) => h.update(bytes),
+            Hasher::Adler32 { a, b } => {
+                const MOD_ADLER: u32 = 65521;
+                for &byte in bytes {
+                    *a = (*a + byte as u32) % MOD_ADLER;
+                    *b = (*b + *a) % MOD_ADLER;
+                }
+            }
+            Hasher::Ssdeep(h) => h.update(bytes),
+            Hasher::Tlsh(h) => h.update(bytes),
+        }
+    }
+
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Md5(h) => format!("{:x}", h.finalize()),
+            Hasher::Sha1(h) => format!("{:x}", h.finalize()),
+            Hasher::Sha256(h) => format!("{:x}", h.finalize()),
+            Hasher::Sha512(h) => format!("{:x}", h.finalize()),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+            Hasher::Crc32(h) => format!("{:08x}", h.finalize()),
+            Hasher::Adler32 { a, b } => format!("{:08x}", (b << 16) | a),
+            Hasher::Ssdeep(mut h) => {
+                h.finalize();
+                h.to_string()
+            }
+            Hasher::Tlsh(h) => match h.build() {
+                Some(tlsh) => String::from_utf8_lossy(&tlsh.hash()).into_owned(),
+                None => String::from("n/a (not enough data)"),
+            },
+        }
+    }
+}
+
+/// Hashes `content[range]` with all `algorithms` on a background thread,
+/// reporting progress along the way
+pub fn spawn_worker(
+    job: u64,
+    content: Arc<[u8]>,
+    range: Range<usize>,
+    algorithms: Vec<HashAlgorithm>,
+    tx: WorkerSender,
+) {
+    thread::spawn(move || {
+        let mut hashers = algorithms
+            .iter()
+            .map(|&algorithm| Hasher::new(algorithm))
+            .collect::<Vec<_>>();
+        let total = range.len();
+        let mut done = 0;
+
+        for chunk in content[range].chunks(CHUNK_SIZE) {
+            for hasher in hashers.iter_mut() {
+                hasher.update(chunk);
+            }
+            done += chunk.len();
+            if tx
+                .send(WorkerMsg::HashProgress { job, done, total })
+                .is_err()
+            {
+                // application is gone
+                return;
+            }
+        }
+
+        let results = algorithms
+            .into_iter()
+            .zip(hashers.into_iter().map(Hasher::finalize))
+            .collect::<Vec<_>>();
+        debug!("hash job {job} done");
+        let _ = tx.send(WorkerMsg::HashDone { job, results });
+    });
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum HashValue {
+    #[default]
+    NotComputed,
+    Computing {
+        job: u64,
+        percent: usize,
+    },
+    Done(String),
+}
+
+/// Hash values of a range of the file, displayed in the file details popup
+#[derive(Debug, Default)]
+pub struct Hashes {
+    pub range: Range<usize>,
+    values: [HashValue; ALGORITHMS.len()],
+    pub selected: usize,
+    next_job: u64,
+}
+
+impl Hashes {
+    /// Registers a new job computing `algorithms` over `range`, and returns
+    /// its identifier. Values of another range are discarded.
+    pub fn start_job(&mut self, range: Range<usize>, algorithms: &[HashAlgorithm]) -> u64 {
+        if range != self.range {
+            self.range = range;
+            self.values = Default::default();
+        }
+
+        let job = self.next_job;
+        self.next_job += 1;
+        for &algorithm in algorithms {
+            self.values[algorithm.index()] = HashValue::Computing { job, percent: 0 };
+        }
+
+        job
+    }
+
+    /// Discards all values, e.g. when the content changed. Results of running
+    /// jobs will be ignored.
+    pub fn clear(&mut self) {
+        self.values = Default::default();
+    }
+
+    pub fn progress(&mut self, job: u64, done: usize, total: usize) {
+        let new_percent = (done * 100).checked_div(total).unwrap_or(100);
+        for value in self.values.iter_mut() {
+            if let HashValue::Computing { job: j, percent } = value
+                && *j == job
+            {
+                *percent = new_percent;
+            }
+        }
+    }
+
+    pub fn done(&mut self, job: u64, results: Vec<(HashAlgorithm, String)>) {
+        for (algorithm, result) in results {
+            let value = &mut self.values[algorithm.index()];
+            // results of a discarded job are ignored
+            if matches!(value, HashValue::Computing { job: j, .. } if *j == job) {
+                *value = HashValue::Done(result);
+            }
+        }
+    }
+
+    pub fn selected_algorithm(&self) -> HashAlgorithm {
+        ALGORITHMS[self.selected]
+    }
+
+    pub fn selected_value(&self) -> Option<&str> {
+        match &self.values[self.selected] {
+            HashValue::Done(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = std::cmp::min(self.selected + 1, ALGORITHMS.len() - 1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn to_lines(&self) -> Vec<Line<'_>> {
+        let mut lines = vec![Line::from(format!(
+            "hashes of {:#x}..{:#x}:",
+            self.range.start, self.range.end
+        ))];
+
+        for (i, algorithm) in ALGORITHMS.iter().enumerate() {
+            let value = match &self.values[i] {
+                HashValue::NotComputed => String::from("-"),
+                HashValue::Computing { percent, .. } => format!("computing.. {percent}%"),
+                HashValue::Done(value) => value.clone(),
+            };
+            let style = if i == self.selected {
+                Style::default().reversed()
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(format!("{:<12} {value}", format!("{algorithm}:"))).style(style));
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_follow_algorithms() {
+        let mut hashes = Hashes::default();
+        let job = hashes.start_job(0..3, &[HashAlgorithm::Crc32, HashAlgorithm::Tlsh]);
+        hashes.done(job, vec![(HashAlgorithm::Crc32, String::from("352441c2"))]);
+        hashes.selected = ALGORITHMS
+            .iter()
+            .position(|&a| a == HashAlgorithm::Crc32)
+            .unwrap();
+        assert_eq!(hashes.selected_algorithm(), HashAlgorithm::Crc32);
+        assert_eq!(hashes.selected_value(), Some("352441c2"));
+    }
+}
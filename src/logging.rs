@@ -5,7 +5,9 @@ use std::fs::OpenOptions;
 
 const LOG_FILE: &str = "hexplore.log";
 
-pub fn init_logs() {
+/// Initializes the logs. When stdout carries the output (e.g. a dump), only
+/// warnings and errors are written to the terminal, on stderr.
+pub fn init_logs(stderr_only: bool) {
     let mut path = env::temp_dir();
     path.push(LOG_FILE);
 
@@ -21,9 +23,17 @@ pub fn init_logs() {
         .build();
 
     let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
-        LevelFilter::Info,
+        if stderr_only {
+            LevelFilter::Warn
+        } else {
+            LevelFilter::Info
+        },
         config_termlogger,
-        TerminalMode::Mixed,
+        if stderr_only {
+            TerminalMode::Stderr
+        } else {
+            TerminalMode::Mixed
+        },
         ColorChoice::Auto,
     )];
 
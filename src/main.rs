@@ -1,14 +1,38 @@
+mod addressing;
+mod analysis;
 mod app;
+mod archive;
+mod bookmarks;
+mod buffer;
 mod cli;
+mod clipboard;
+mod command;
+mod compression;
+#[cfg(unix)]
+mod device;
+mod diff;
 mod display;
+mod dump;
+mod encoding;
 mod events;
+mod export;
 mod file;
+mod firmware;
+mod hash;
 mod logging;
+mod minimap;
 mod movement;
 mod popup;
+mod process;
+mod regions;
+mod revert;
+mod stats;
+mod strings;
+mod watch;
+mod worker;
 
 use app::App;
-use clap::Parser;
+use clap::{CommandFactory, Parser, error::ErrorKind};
 use log::{debug, error, info};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
@@ -17,34 +41,68 @@ use ratatui::crossterm::execute;
 use ratatui::crossterm::terminal::{
     EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
 };
-use std::io;
-use std::process;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
 
 use logging::init_logs;
 
 fn main() {
-    init_logs();
+    let args = cli::Args::parse();
+    init_logs(args.dump.is_some() || args.revert.is_some() || args.export.is_some());
     debug!("logs initialized successfully");
 
-    if let Err(err) = actual_main() {
+    if let Err(err) = actual_main(args) {
         error!("error: {err}");
-        process::exit(1);
+        std::process::exit(1);
     }
 
     info!("Bye!");
 }
 
-fn actual_main() -> io::Result<()> {
-    let args = cli::Args::parse();
+fn actual_main(args: cli::Args) -> io::Result<()> {
+    let (files, other) = match (args.diff.clone(), args.pid) {
+        (Some(mut files), _) => {
+            let other = files.pop();
+            (files, other)
+        }
+        (None, Some(pid)) => (vec![process::mem_path(pid)], None),
+        // data piped or redirected without file argument is read from stdin
+        (None, None) if args.files.is_empty() && !io::stdin().is_terminal() => {
+            (vec![String::from(file::STDIN_PATH)], None)
+        }
+        (None, None) if args.files.is_empty() => cli::Args::command()
+            .error(ErrorKind::MissingRequiredArgument, "no input file given")
+            .exit(),
+        (None, None) => (args.files.clone(), None),
+    };
+    if let Some(format) = args.export {
+        return export_files(&files, format, &args);
+    }
+    if let Some(options) = args.dump_options() {
+        return match args.revert {
+            Some(_) => revert_files(&files, &options),
+            None => dump_files(&files, &options),
+        };
+    }
 
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
     let term_size = terminal.size()?;
     debug!("terminal size: {term_size}");
     let mut app = App::new(
-        args.file,
+        files,
+        other,
+        args.align,
+        args.allow_write,
+        args.follow,
         args.blocksize,
         (term_size.width, term_size.height),
     )?;
+    app.display = args.display_options();
+    app.char_table = args.table.clone();
+    app.export = args.export_options();
+    if let Some(address_map) = args.address_map() {
+        app.set_address_map(address_map);
+    }
     debug!("app initialized successfully");
 
     init_terminal_state()?;
@@ -54,6 +112,65 @@ fn actual_main() -> io::Result<()> {
     res
 }
 
+/// Writes the dump of each file to stdout, one after the other
+fn dump_files(files: &[String], options: &dump::DumpOptions) -> io::Result<()> {
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for file in files {
+        let fileinfo = file::FileInfo::new(file)?;
+        let res = dump::dump(&mut out, &fileinfo.content, options).and_then(|_| out.flush());
+        match res {
+            // the reader of the pipe is gone, e.g. `head`
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the bytes of each file as a literal to stdout, one after the other
+fn export_files(
+    files: &[String],
+    format: export::ExportFormat,
+    args: &cli::Args,
+) -> io::Result<()> {
+    let options = args.export_options();
+    let mut out = io::stdout().lock();
+    for file in files {
+        let fileinfo = file::FileInfo::new(file)?;
+        let content = &fileinfo.content;
+        let start = std::cmp::min(args.offset.unwrap_or(0), content.len());
+        let end = match args.length {
+            Some(length) => std::cmp::min(start.saturating_add(length), content.len()),
+            None => content.len(),
+        };
+        let text = export::export(&content[start..end], format, &options);
+        match out.write_all(text.as_bytes()).and_then(|_| out.flush()) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes the bytes rebuilt from each dump to stdout, one after the other
+fn revert_files(files: &[String], options: &dump::DumpOptions) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    for file in files {
+        let (name, input) = if file == file::STDIN_PATH {
+            ("stdin", io::read_to_string(io::stdin())?)
+        } else {
+            (file.as_str(), fs::read_to_string(file)?)
+        };
+        let content = revert::revert(&input, options)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{name}: {err}")))?;
+        match out.write_all(&content).and_then(|_| out.flush()) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
 fn init_terminal_state() -> io::Result<()> {
     enable_raw_mode()?;
     execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
@@ -0,0 +1,343 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::thread;
+
+use log::debug;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+use crate::{
+    App,
+    analysis::{self, ByteClass},
+    worker::{WorkerMsg, WorkerSender},
+};
+
+pub const MINIMAP_WIDTH: u16 = 2;
+const MIN_BLOCK_SIZE: usize = 256;
+/// Upper bound on the number of blocks, so that huge files keep a small summary
+const MAX_BLOCKS: usize = 1 << 16;
+/// Number of blocks computed before sending them to the application
+const BATCH_SIZE: usize = 512;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlockSummary {
+    pub entropy: f32,
+    pub class: ByteClass,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapMode {
+    #[default]
+    Entropy,
+    ByteClass,
+}
+
+#[derive(Debug, Default)]
+pub struct Minimap {
+    pub visible: bool,
+    pub mode: MinimapMode,
+    pub block_size: usize,
+    pub nb_blocks: usize,
+    /// Summaries computed so far, filled in by the background worker
+    pub blocks: Vec<BlockSummary>,
+    /// Area where the minimap was last rendered, used for mouse handling
+    pub area: Rect,
+    /// Offsets of the file shown on each row, set before rendering. The rows
+    /// cover the view like the scrollbar, gaps being left blank.
+    pub rows: Vec<Range<usize>>,
+}
+
+impl Minimap {
+    pub fn new(filesize: usize) -> Self {
+        let block_size = std::cmp::max(MIN_BLOCK_SIZE, filesize.div_ceil(MAX_BLOCKS));
+        Minimap {
+            block_size,
+            nb_blocks: filesize.div_ceil(block_size),
+            ..Minimap::default()
+        }
+    }
+
+    /// Starts computing block summaries of `content` on a background thread
+    pub fn spawn_worker(&self, content: Arc<[u8]>, tx: WorkerSender) {
+        let block_size = self.block_size;
+        thread::spawn(move || {
+            let mut first = 0;
+            for batch in content.chunks(block_size * BATCH_SIZE) {
+                let blocks = batch
+                    .chunks(block_size)
+                    .map(|block| BlockSummary {
+                        entropy: analysis::shannon_entropy(block) as f32,
+                        class: analysis::classify(block),
+                    })
+                    .collect::<Vec<_>>();
+                let len = blocks.len();
+                if tx.send(WorkerMsg::MinimapBlocks { first, blocks }).is_err() {
+                    // application is gone
+                    return;
+                }
+                first += len;
+            }
+            debug!("minimap: {first} blocks computed");
+        });
+    }
+
+    pub fn update(&mut self, first: usize, blocks: Vec<BlockSummary>) {
+        // batches are sent in order, but stay safe if they are not
+        if self.blocks.len() < first {
+            self.blocks.resize(first, BlockSummary::default());
+        }
+        self.blocks.truncate(first);
+        self.blocks.extend(blocks);
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            MinimapMode::Entropy => MinimapMode::ByteClass,
+            MinimapMode::ByteClass => MinimapMode::Entropy,
+        };
+    }
+
+    /// Blocks holding the bytes at `offsets`
+    fn blocks_of(&self, offsets: &Range<usize>) -> Range<usize> {
+        if self.block_size == 0 || offsets.is_empty() {
+            return 0..0;
+        }
+        let end = std::cmp::min(offsets.end.div_ceil(self.block_size), self.nb_blocks);
+        offsets.start / self.block_size..end
+    }
+
+    /// Color of the row showing the bytes at `offsets`, `None` for a gap or
+    /// blocks not computed yet
+    fn row_color(&self, offsets: &Range<usize>) -> Option<Color> {
+        let Range { start, end } = self.blocks_of(offsets);
+        if start >= end || end > self.blocks.len() {
+            return None;
+        }
+        let blocks = &self.blocks[start..end];
+
+        let color = match self.mode {
+            MinimapMode::Entropy => {
+                let max = blocks.iter().map(|b| b.entropy).fold(0.0, f32::max);
+                entropy_color(max)
+            }
+            MinimapMode::ByteClass => {
+                // a row is only padding if all its blocks are
+                let class = if blocks.iter().all(|b| b.class == blocks[0].class) {
+                    blocks[0].class
+                } else if blocks.iter().any(|b| b.class == ByteClass::Binary) {
+                    ByteClass::Binary
+                } else {
+                    ByteClass::Ascii
+                };
+                class_color(class)
+            }
+        };
+
+        Some(color)
+    }
+
+    /// Converts a row in the minimap area into a ratio of the view, between 0 and 1
+    pub fn row_to_ratio(&self, row: u16) -> Option<f64> {
+        if !self.visible || self.area.height == 0 {
+            return None;
+        }
+        let row = row.checked_sub(self.area.y)?;
+        let row = std::cmp::min(row, self.area.height - 1);
+        Some(row as f64 / self.area.height as f64)
+    }
+
+    pub fn contains(&self, column: u16, row: u16) -> bool {
+        self.visible
+            && column >= self.area.x
+            && column < self.area.x + self.area.width
+            && row >= self.area.y
+            && row < self.area.y + self.area.height
+    }
+}
+
+fn entropy_color(entropy: f32) -> Color {
+    match entropy {
+        e if e < 1.0 => Color::DarkGray,
+        e if e < 3.0 => Color::Blue,
+        e if e < 5.0 => Color::Green,
+        e if e < 7.0 => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+fn class_color(class: ByteClass) -> Color {
+    match class {
+        ByteClass::Zero => Color::DarkGray,
+        ByteClass::Ff => Color::White,
+        ByteClass::Ascii => Color::Cyan,
+        ByteClass::Binary => Color::Magenta,
+    }
+}
+
+impl Widget for &Minimap {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.nb_blocks == 0 {
+            return;
+        }
+
+        for (row, offsets) in self.rows.iter().take(area.height as usize).enumerate() {
+            let Some(color) = self.row_color(offsets) else {
+                continue;
+            };
+            for col in area.left()..area.right() {
+                if let Some(cell) = buf.cell_mut((col, area.y + row as u16)) {
+                    cell.set_style(Style::default().bg(color));
+                }
+            }
+        }
+    }
+}
+
+impl App {
+    /// Offsets of the opened file shown on each row of a minimap `height`
+    /// rows tall, in the same positions of the view as [`App::scroll_to_ratio`]
+    pub fn minimap_rows(&self, height: u16) -> Vec<Range<usize>> {
+        let len = self.data_size();
+        let height = height as usize;
+        // `row * len / height` without overflowing on the span of a process
+        let position = |row: usize| row * (len / height) + row * (len % height) / height;
+        (0..height)
+            .map(|row| {
+                let start = position(row);
+                let end = std::cmp::min(std::cmp::max(position(row + 1), start + 1), len);
+                match &self.buffer.diff {
+                    Some(diff) => diff.left_range(start..end),
+                    None => self.buffer.offset_range(start..end),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        addressing::{AddressMap, Segment},
+        diff::Diff,
+        file::FileInfo,
+    };
+
+    fn summary(entropy: f32, class: ByteClass) -> BlockSummary {
+        BlockSummary { entropy, class }
+    }
+
+    #[test]
+    fn blocks_are_updated_by_batches() {
+        let mut minimap = Minimap::new(4 * MIN_BLOCK_SIZE);
+        assert_eq!((minimap.block_size, minimap.nb_blocks), (MIN_BLOCK_SIZE, 4));
+        minimap.update(2, vec![summary(8.0, ByteClass::Binary); 2]);
+        assert_eq!(minimap.blocks.len(), 4);
+        minimap.update(0, vec![summary(0.0, ByteClass::Zero); 2]);
+        assert_eq!(minimap.blocks.len(), 2);
+
+        // huge files keep a bounded number of blocks
+        let minimap = Minimap::new(1 << 40);
+        assert_eq!(minimap.nb_blocks, MAX_BLOCKS);
+    }
+
+    #[test]
+    fn rows_show_the_blocks_of_their_offsets() {
+        let mut minimap = Minimap::new(4 * MIN_BLOCK_SIZE);
+        minimap.update(
+            0,
+            vec![
+                summary(0.0, ByteClass::Zero),
+                summary(4.0, ByteClass::Ascii),
+                summary(7.5, ByteClass::Binary),
+            ],
+        );
+        assert_eq!(minimap.row_color(&(0..256)), Some(Color::DarkGray));
+        // the highest entropy of the row
+        assert_eq!(minimap.row_color(&(0..300)), Some(Color::Green));
+        assert_eq!(minimap.row_color(&(256..768)), Some(Color::Red));
+        // gap, and block not computed yet
+        assert_eq!(minimap.row_color(&(0..0)), None);
+        assert_eq!(minimap.row_color(&(800..900)), None);
+
+        minimap.toggle_mode();
+        assert_eq!(minimap.row_color(&(0..256)), Some(Color::DarkGray));
+        assert_eq!(minimap.row_color(&(0..512)), Some(Color::Cyan));
+        assert_eq!(minimap.row_color(&(0..768)), Some(Color::Magenta));
+    }
+
+    #[test]
+    fn rows_to_ratio() {
+        let mut minimap = Minimap {
+            area: Rect::new(10, 2, 2, 4),
+            ..Minimap::default()
+        };
+        assert_eq!(minimap.row_to_ratio(3), None);
+        minimap.visible = true;
+        assert_eq!(minimap.row_to_ratio(2), Some(0.0));
+        assert_eq!(minimap.row_to_ratio(5), Some(0.75));
+        assert_eq!(minimap.row_to_ratio(9), Some(0.75));
+        assert!(minimap.contains(11, 5) && !minimap.contains(12, 5));
+    }
+
+    fn app(content: &[u8]) -> App {
+        let mut app = App::default();
+        app.buffer.fileinfo = FileInfo {
+            content: Arc::from(content),
+            size: content.len(),
+            ..FileInfo::default()
+        };
+        app.buffer.minimap = Minimap::new(content.len());
+        app
+    }
+
+    #[test]
+    fn rows_and_clicks_follow_the_virtual_addresses() {
+        let mut app = app(&[0; 1024]);
+        app.buffer.set_address_map(AddressMap::new(vec![
+            Segment {
+                offset: 0,
+                len: 512,
+                address: 0x1000,
+            },
+            Segment {
+                offset: 512,
+                len: 512,
+                address: 0x3000,
+            },
+        ]));
+        // 0x2200 addresses, the rows in between show the gap
+        assert_eq!(app.minimap_rows(4), [0..512, 0..0, 0..0, 512..1024]);
+
+        // the cursor moves to the start of the last row
+        app.scroll_to_ratio(0.75);
+        assert_eq!(app.buffer.scroll_pos, 0x2200 * 3 / 4 / 16);
+        assert_eq!(app.buffer.cursor, 0x2200 * 3 / 4);
+        assert_eq!(app.cursor_offset(), None);
+        // line 516 of 544, address 0x3040
+        app.scroll_to_ratio(0.95);
+        assert_eq!(app.cursor_offset(), Some(0x240));
+    }
+
+    #[test]
+    fn rows_and_clicks_follow_the_aligned_diff() {
+        let left = (0..=255).cycle().take(1024).collect::<Vec<u8>>();
+        let right = [&[0xaa; 1024][..], &left].concat();
+        let mut app = app(&left);
+        let other = FileInfo {
+            size: right.len(),
+            content: Arc::from(right),
+            ..FileInfo::default()
+        };
+        app.buffer.diff = Some(Diff::new_aligned(&left, other));
+        // the inserted bytes come first in the view
+        assert_eq!(app.minimap_rows(2), [0..0, 0..1024]);
+        app.scroll_to_ratio(0.5);
+        assert_eq!(app.buffer.cursor, 1024);
+        assert_eq!(app.cursor_offset(), Some(0));
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use crate::App;
 
 impl App {
@@ -5,28 +7,203 @@ impl App {
         self.frame_size = (new_width, new_height);
     }
 
+    /// Size of the displayed data, the aligned view in diff mode
+    pub fn data_size(&self) -> usize {
+        match &self.buffer.diff {
+            Some(diff) => diff.view_len(),
+            None => self.buffer.view_len(),
+        }
+    }
+
+    /// Moves the cursor to the next (or previous) differing range in diff mode
+    pub fn move_to_difference(&mut self, forward: bool) {
+        let Some(diff) = &self.buffer.diff else {
+            return;
+        };
+        let range = if forward {
+            diff.next_range(self.buffer.cursor)
+        } else {
+            diff.previous_range(self.buffer.cursor)
+        };
+        if let Some(range) = range {
+            self.move_cursor_to(range.start);
+        }
+    }
+
+    /// Moves the cursor to the start of the next (or previous) mapped range:
+    /// memory mapping of a process, data of a firmware image or segment
+    pub fn move_to_range(&mut self, forward: bool) {
+        if self.buffer.diff.is_some() {
+            return;
+        }
+        let cursor = self.buffer.cursor;
+        let starts = self.buffer.range_starts();
+        let start = if forward {
+            starts.into_iter().find(|&start| start > cursor)
+        } else {
+            starts.into_iter().rev().find(|&start| start < cursor)
+        };
+        if let Some(start) = start {
+            self.move_cursor_to(start);
+        }
+    }
+
+    /// Number of lines of data displayed at once
+    pub fn page_height(&self) -> usize {
+        (self.frame_size.1 as usize)
+            .saturating_sub(self.vertical_margin + self.header_height() as usize)
+    }
+
+    /// Moves the cursor to `offset` (clamped to the file) and scrolls so that
+    /// it stays visible
+    pub fn move_cursor_to(&mut self, offset: usize) {
+        if self.data_size() == 0 {
+            return;
+        }
+        self.buffer.cursor = std::cmp::min(offset, self.data_size() - 1);
+        self.scroll_to_cursor();
+    }
+
+    /// Position in the view of `offset` in the opened file, `None` if it is
+    /// not mapped to a virtual address
+    pub fn position_of_offset(&self, offset: usize) -> Option<usize> {
+        match &self.buffer.diff {
+            Some(diff) => Some(diff.position_of(offset)),
+            None => self.buffer.position_of(offset),
+        }
+    }
+
+    /// Offset in the opened file of the byte at the cursor, `None` past its
+    /// end or in a gap of the virtual addresses
+    pub fn cursor_offset(&self) -> Option<usize> {
+        let cursor = self.buffer.cursor;
+        let offset = match &self.buffer.diff {
+            Some(diff) => Some(diff.left_range(cursor..cursor).start),
+            None => self.buffer.offset_at(cursor),
+        };
+        offset.filter(|&offset| offset < self.buffer.fileinfo.size)
+    }
+
+    /// Moves the cursor to `offset` in the opened file
+    pub fn move_cursor_to_offset(&mut self, offset: usize) {
+        match self.position_of_offset(offset) {
+            Some(pos) => self.move_cursor_to(pos),
+            None => {
+                self.message = Some(format!(
+                    "offset {offset:#x} is not mapped to a virtual address"
+                ));
+            }
+        }
+    }
+
+    pub fn scroll_to_cursor(&mut self) {
+        let line = self.buffer.cursor / self.bytes_per_row;
+        let height = std::cmp::max(self.page_height(), 1);
+        if line < self.buffer.scroll_pos {
+            self.buffer.scroll_pos = line;
+        } else if line >= self.buffer.scroll_pos + height {
+            self.buffer.scroll_pos = line + 1 - height;
+        }
+    }
+
+    /// Starts a selection at the cursor, or stops the current one
+    pub fn toggle_selection(&mut self) {
+        self.buffer.selection_anchor = match self.buffer.selection_anchor {
+            Some(_) => None,
+            None => Some(self.buffer.cursor),
+        };
+    }
+
+    /// Returns the selected bytes range, from the anchor to the cursor (both
+    /// included)
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.buffer.selection_anchor?;
+        let start = std::cmp::min(anchor, self.buffer.cursor);
+        let end = std::cmp::max(anchor, self.buffer.cursor) + 1;
+        Some(start..std::cmp::min(end, self.data_size()))
+    }
+
+    /// Returns the selection, or the whole opened file if nothing is selected
+    pub fn selection_or_file(&self) -> Range<usize> {
+        match (self.selection(), &self.buffer.diff) {
+            // in diff mode, the selection is in the aligned view
+            (Some(selection), Some(diff)) => diff.left_range(selection),
+            // the selected virtual addresses may hold gaps
+            (Some(selection), None) => self.buffer.offset_range(selection),
+            (None, _) => 0..self.buffer.fileinfo.size,
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.move_cursor_to(self.buffer.cursor.saturating_sub(1));
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        self.move_cursor_to(self.buffer.cursor + 1);
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        if self.buffer.cursor + self.bytes_per_row < self.data_size() {
+            self.move_cursor_to(self.buffer.cursor + self.bytes_per_row);
+        }
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        if self.buffer.cursor >= self.bytes_per_row {
+            self.move_cursor_to(self.buffer.cursor - self.bytes_per_row);
+        }
+    }
+
+    pub fn move_line_down(&mut self) {
+        if (self.buffer.scroll_pos + 1) * self.bytes_per_row < self.data_size() {
+            self.buffer.scroll_pos += 1;
+        }
+    }
+
+    pub fn move_line_up(&mut self) {
+        self.buffer.scroll_pos = self.buffer.scroll_pos.saturating_sub(1);
+    }
+
+    /// Scrolls to the line at `ratio` (between 0 and 1) of the view, the
+    /// cursor moving to its start
+    pub fn scroll_to_ratio(&mut self, ratio: f64) {
+        let nb_lines = self.data_size().div_ceil(self.bytes_per_row);
+        let line = (ratio * nb_lines as f64) as usize;
+        self.buffer.scroll_pos = std::cmp::min(line, nb_lines.saturating_sub(1));
+        self.move_cursor_to(self.buffer.scroll_pos * self.bytes_per_row);
+    }
+
     pub fn move_page_half_up(&mut self) {
-        let height = self.frame_size.1 as usize - self.vertical_margin;
-        self.scroll_pos = self.scroll_pos.saturating_sub(height / 2);
+        self.shift_up(self.page_height() / 2);
     }
 
     pub fn move_page_half_down(&mut self) {
-        let height = self.frame_size.1 as usize;
-        let shift = (height - self.vertical_margin) / 2;
-        if (self.scroll_pos + shift) * self.bytes_per_row < self.fileinfo.size {
-            self.scroll_pos += shift;
-        }
+        self.shift_down(self.page_height() / 2);
     }
 
     pub fn move_page_up(&mut self) {
-        let height = self.frame_size.1 as usize - self.vertical_margin;
-        self.scroll_pos = self.scroll_pos.saturating_sub(height);
+        self.shift_up(self.page_height());
     }
 
     pub fn move_page_down(&mut self) {
-        let height = self.frame_size.1 as usize - self.vertical_margin;
-        if (self.scroll_pos + height) * self.bytes_per_row < self.fileinfo.size {
-            self.scroll_pos += height;
+        self.shift_down(self.page_height());
+    }
+
+    /// Scrolls `lines` up, the cursor following the view
+    fn shift_up(&mut self, lines: usize) {
+        self.buffer.scroll_pos = self.buffer.scroll_pos.saturating_sub(lines);
+        self.move_cursor_to(
+            self.buffer
+                .cursor
+                .saturating_sub(lines * self.bytes_per_row),
+        );
+    }
+
+    /// Scrolls `lines` down, the cursor following the view
+    fn shift_down(&mut self, lines: usize) {
+        if (self.buffer.scroll_pos + lines) * self.bytes_per_row < self.data_size() {
+            self.buffer.scroll_pos += lines;
+            self.move_cursor_to(self.buffer.cursor + lines * self.bytes_per_row);
         }
     }
 }
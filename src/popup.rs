@@ -13,6 +13,8 @@ pub struct Popup<'a> {
     title: Line<'a>,
     #[setters(into)]
     content: Text<'a>,
+    #[setters(into)]
+    footer: Line<'a>,
     border_style: Style,
     title_style: Style,
     style: Style,
@@ -24,6 +26,7 @@ impl Widget for Popup<'_> {
         let block = Block::new()
             .title(self.title)
             .title_style(self.title_style)
+            .title_bottom(self.footer)
             .borders(Borders::ALL)
             .border_style(self.border_style);
         Paragraph::new(self.content)
@@ -0,0 +1,206 @@
+use std::{fs, io, ops::Range};
+#[cfg(unix)]
+use std::{fs::File, os::unix::fs::FileExt};
+
+use log::debug;
+
+/// Reads are done page by page once a bigger read failed
+const PAGE_SIZE: usize = 4096;
+/// Amount of bytes read at once in a mapping
+const CHUNK_SIZE: usize = 1 << 20;
+/// Mappings bigger than this (usually reserved address space) are listed but
+/// not read
+const MAX_MAPPING_LEN: usize = 1 << 30;
+
+/// A memory mapping of a process, as listed in `/proc/<pid>/maps`
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    /// Virtual addresses of the mapping
+    pub start: usize,
+    pub end: usize,
+    /// Permissions, e.g. `r-xp`
+    pub perms: String,
+    /// Backing file, or pseudo path like `[heap]`, empty if anonymous
+    pub path: String,
+    /// Offset of the mapping in the content
+    pub offset: usize,
+    /// Number of bytes of the mapping in the content, 0 if it was not read
+    pub len: usize,
+    /// Pages which could not be read (zeroed in the content), as ranges of
+    /// offsets in the content
+    pub unreadable: Vec<Range<usize>>,
+}
+
+impl Mapping {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?.to_string();
+        // offset, device and inode are not needed
+        let path = fields.nth(3).unwrap_or_default().to_string();
+
+        Some(Mapping {
+            start: usize::from_str_radix(start, 16).ok()?,
+            end: usize::from_str_radix(end, 16).ok()?,
+            perms,
+            path,
+            ..Mapping::default()
+        })
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.perms.starts_with('r')
+    }
+
+    /// Virtual address of `offset` in the content, if part of this mapping
+    pub fn address_of(&self, offset: usize) -> Option<usize> {
+        (self.offset..self.offset + self.len)
+            .contains(&offset)
+            .then(|| self.start + offset - self.offset)
+    }
+}
+
+/// Returns the pid of `path` if it is the memory of a process
+/// (`/proc/<pid>/mem`)
+pub fn pid_of_mem_path(path: &str) -> Option<u32> {
+    path.strip_prefix("/proc/")?
+        .strip_suffix("/mem")?
+        .parse()
+        .ok()
+}
+
+pub fn mem_path(pid: u32) -> String {
+    format!("/proc/{pid}/mem")
+}
+
+/// Name of the process, from `/proc/<pid>/comm`
+pub fn process_name(pid: u32) -> String {
+    fs::read_to_string(format!("/proc/{pid}/comm"))
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Reads the readable mappings of the process `pid`, one after the other.
+/// Pages which cannot be read are zeroed and reported in the mappings.
+#[cfg(unix)]
+pub fn read_memory(pid: u32) -> io::Result<(Vec<u8>, Vec<Mapping>)> {
+    let maps = fs::read_to_string(format!("/proc/{pid}/maps"))?;
+    let mut mappings = maps.lines().filter_map(Mapping::parse).collect::<Vec<_>>();
+    let mem = File::open(mem_path(pid))?;
+
+    let mut content = vec![];
+    for mapping in mappings.iter_mut() {
+        mapping.offset = content.len();
+        let len = mapping.end - mapping.start;
+        if !mapping.is_readable() || len > MAX_MAPPING_LEN {
+            continue;
+        }
+
+        content.resize(mapping.offset + len, 0);
+        let bytes = &mut content[mapping.offset..];
+        for chunk_start in (0..len).step_by(CHUNK_SIZE) {
+            let chunk_end = std::cmp::min(chunk_start + CHUNK_SIZE, len);
+            let address = (mapping.start + chunk_start) as u64;
+            if mem
+                .read_exact_at(&mut bytes[chunk_start..chunk_end], address)
+                .is_ok()
+            {
+                continue;
+            }
+            // some pages of the chunk are not readable (guard pages, I/O
+            // mappings, ...), find which ones
+            for page_start in (chunk_start..chunk_end).step_by(PAGE_SIZE) {
+                let page_end = std::cmp::min(page_start + PAGE_SIZE, chunk_end);
+                let page = &mut bytes[page_start..page_end];
+                if mem
+                    .read_exact_at(page, (mapping.start + page_start) as u64)
+                    .is_err()
+                {
+                    page.fill(0);
+                    push_range(
+                        &mut mapping.unreadable,
+                        mapping.offset + page_start..mapping.offset + page_end,
+                    );
+                }
+            }
+        }
+        mapping.len = len;
+    }
+    debug!(
+        "{} bytes read from {} mappings of process {pid}",
+        content.len(),
+        mappings.len()
+    );
+
+    Ok((content, mappings))
+}
+
+/// Pushes `range`, merging it with the last one if they are contiguous
+#[cfg(unix)]
+fn push_range(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::process::{Child, Command};
+
+    use super::*;
+
+    /// Kills the child even if the test fails
+    struct Sleeper(Child);
+
+    impl Drop for Sleeper {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    fn parse_maps_line() {
+        let line = "7f1c2a000000-7f1c2a021000 r-xp 00002000 08:01 1234   /usr/lib/libc.so.6";
+        let mapping = Mapping::parse(line).unwrap();
+        assert_eq!(mapping.start, 0x7f1c_2a00_0000);
+        assert_eq!(mapping.end, 0x7f1c_2a02_1000);
+        assert_eq!(mapping.perms, "r-xp");
+        assert_eq!(mapping.path, "/usr/lib/libc.so.6");
+        let anonymous = Mapping::parse("1000-2000 rw-p 00000000 00:00 0").unwrap();
+        assert_eq!(anonymous.path, "");
+    }
+
+    #[test]
+    fn read_child_memory() {
+        let child = Sleeper(Command::new("sleep").arg("10").spawn().unwrap());
+        let pid = child.0.id();
+        // the program is mapped once `sleep` is executed
+        let exe = (0..100)
+            .find_map(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                fs::read_link(format!("/proc/{pid}/exe"))
+                    .ok()
+                    .filter(|exe| exe.ends_with("sleep"))
+            })
+            .unwrap();
+
+        let (content, mappings) = read_memory(pid).unwrap();
+        // the first mapping of the program holds its ELF header
+        let program = mappings
+            .iter()
+            .find(|mapping| mapping.path == exe.to_string_lossy())
+            .unwrap();
+        assert!(program.len > 0);
+        assert_eq!(&content[program.offset..program.offset + 4], b"\x7fELF");
+        assert_eq!(program.address_of(program.offset), Some(program.start));
+
+        // pages which cannot be read through `mem` are gaps, not errors
+        for vvar in mappings.iter().filter(|m| m.path.starts_with("[vvar")) {
+            let range = vvar.offset..vvar.offset + vvar.len;
+            assert!(content[range.clone()].iter().all(|&b| b == 0));
+            assert_eq!(vvar.unreadable, [range]);
+        }
+    }
+}
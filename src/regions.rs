@@ -0,0 +1,324 @@
+use std::fs;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use log::debug;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+
+/// Suffix of the project file, next to the opened file
+const PROJECT_SUFFIX: &str = ".hexplore.toml";
+/// Colors given in turn to the regions labeled without a color
+const COLORS: [&str; 6] = ["blue", "magenta", "cyan", "green", "red", "yellow"];
+
+/// A labeled range of bytes of the opened file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    pub label: String,
+    pub offset: usize,
+    pub len: usize,
+    /// Name of the color tinting the bytes (e.g. `blue`, `#ff8000`)
+    pub color: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+impl Region {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    /// `None` for an unknown color, refused when the region is added or loaded
+    pub fn color(&self) -> Option<Color> {
+        Color::from_str(&self.color).ok()
+    }
+}
+
+/// Content of the project file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Project {
+    #[serde(default, rename = "region")]
+    regions: Vec<Region>,
+}
+
+/// Regions of a buffer, saved to a project file next to the opened file
+/// which can be shared along with it
+#[derive(Debug, Default)]
+pub struct Regions {
+    pub list: Vec<Region>,
+    /// Project file, `None` when the content is not a file on disk
+    path: Option<PathBuf>,
+    /// Why the project file could not be read, it is then never overwritten
+    pub error: Option<String>,
+}
+
+impl Regions {
+    /// Reads the project file of the file at `path`, if any
+    pub fn load(path: &str) -> Self {
+        let path = PathBuf::from(format!("{path}{PROJECT_SUFFIX}"));
+        let mut regions = Regions::default();
+        match fs::read_to_string(&path) {
+            Ok(text) => match toml::from_str::<Project>(&text) {
+                Ok(project) => {
+                    debug!(
+                        "{} regions loaded from '{}'",
+                        project.regions.len(),
+                        path.display()
+                    );
+                    // the others are displayed, but the file is not overwritten
+                    let (valid, invalid): (Vec<_>, Vec<_>) = project
+                        .regions
+                        .into_iter()
+                        .partition(|region| region.color().is_some());
+                    if let Some(region) = invalid.first() {
+                        regions.error = Some(format!(
+                            "invalid project file '{}': unknown color '{}' of region '{}'",
+                            path.display(),
+                            region.color,
+                            region.label
+                        ));
+                    }
+                    regions.list = valid;
+                }
+                Err(err) => {
+                    regions.error = Some(format!(
+                        "invalid project file '{}': {}",
+                        path.display(),
+                        err.message()
+                    ));
+                }
+            },
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                regions.error = Some(format!("failed to read '{}': {err}", path.display()));
+            }
+            Err(_) => {}
+        }
+        regions.path = Some(path);
+        regions
+    }
+
+    /// Innermost region holding `offset`
+    pub fn at(&self, offset: usize) -> Option<&Region> {
+        self.list
+            .iter()
+            .filter(|region| region.range().contains(&offset))
+            .min_by_key(|region| region.len)
+    }
+
+    /// Color of the next region labeled without a color
+    pub fn next_color(&self) -> &'static str {
+        COLORS[self.list.len() % COLORS.len()]
+    }
+
+    /// Adds a region, replacing the one with the same label
+    pub fn add(&mut self, region: Region) -> Result<(), String> {
+        if region.color().is_none() {
+            return Err(format!("unknown color '{}'", region.color));
+        }
+        self.list.retain(|r| r.label != region.label);
+        self.list.push(region);
+        self.list
+            .sort_by_key(|r| (r.offset, std::cmp::Reverse(r.len)));
+        self.save()
+    }
+
+    pub fn remove(&mut self, label: &str) -> Result<(), String> {
+        self.list.retain(|r| r.label != label);
+        self.save()
+    }
+
+    /// Writes the regions to the project file, only for a file on disk
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Err(String::from("the regions of a file on disk only are saved"));
+        };
+        if let Some(err) = &self.error {
+            return Err(format!("{err}, it is not overwritten"));
+        }
+        let project = Project {
+            regions: self.list.clone(),
+        };
+        let text = toml::to_string(&project).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| format!("failed to write '{}': {err}", path.display()))
+    }
+}
+
+impl App {
+    /// Labels the selection, or the whole file, as a region
+    pub fn add_region(&mut self, label: &str, color: Option<&str>, note: &str) {
+        let range = self.selection_or_file();
+        if range.is_empty() {
+            self.message = Some(String::from("no byte to label"));
+            return;
+        }
+        let regions = &mut self.buffer.regions;
+        let color = color.unwrap_or_else(|| regions.next_color()).to_string();
+        let region = Region {
+            label: label.to_string(),
+            offset: range.start,
+            len: range.len(),
+            color,
+            note: note.to_string(),
+        };
+        let len = region.len;
+        self.message = Some(match regions.add(region) {
+            Ok(()) => format!("{len} bytes labeled '{label}'"),
+            Err(err) => format!("'{label}' is not saved: {err}"),
+        });
+    }
+
+    /// Removes the region `label`, or the one at the cursor
+    pub fn remove_region(&mut self, label: Option<&str>) {
+        let label = match label {
+            Some(label) => label.to_string(),
+            None => match self.region_at_cursor() {
+                Some(region) => region.label.clone(),
+                None => {
+                    self.message = Some(String::from("no region at the cursor"));
+                    return;
+                }
+            },
+        };
+        if self.buffer.regions.list.iter().all(|r| r.label != label) {
+            self.message = Some(format!("no region '{label}'"));
+            return;
+        }
+        self.message = Some(match self.buffer.regions.remove(&label) {
+            Ok(()) => format!("region '{label}' removed"),
+            Err(err) => format!("removal of '{label}' is not saved: {err}"),
+        });
+    }
+
+    /// Innermost region holding the byte at the cursor
+    pub fn region_at_cursor(&self) -> Option<&Region> {
+        self.buffer.regions.at(self.cursor_offset()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path of a file whose project file is written by the test
+    fn file(test: &str) -> String {
+        let path =
+            std::env::temp_dir().join(format!("hexplore-regions-{}-{test}", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        let _ = fs::remove_file(project(&path));
+        path
+    }
+
+    fn project(path: &str) -> String {
+        format!("{path}{PROJECT_SUFFIX}")
+    }
+
+    fn region(label: &str, range: Range<usize>, color: &str) -> Region {
+        Region {
+            label: label.to_string(),
+            offset: range.start,
+            len: range.len(),
+            color: color.to_string(),
+            note: String::new(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = file("round_trip");
+        let mut regions = Regions::load(&path);
+        assert_eq!(regions.error, None);
+        assert!(regions.list.is_empty());
+        regions
+            .add(region("payload", 0x10..0x40, "#ff8000"))
+            .unwrap();
+        regions
+            .add(Region {
+                note: String::from("magic and version"),
+                ..region("header", 0..0x10, "green")
+            })
+            .unwrap();
+        regions.add(region("magic", 0..4, "red")).unwrap();
+        // sorted by offset, the outer region first
+        let labels = regions
+            .list
+            .iter()
+            .map(|r| r.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["header", "magic", "payload"]);
+        assert_eq!(regions.at(2).unwrap().label, "magic");
+        assert_eq!(regions.at(8).unwrap().label, "header");
+        assert_eq!(regions.at(0x40), None);
+
+        let loaded = Regions::load(&path);
+        assert_eq!(loaded.error, None);
+        assert_eq!(loaded.list, regions.list);
+        assert_eq!(loaded.list[0].note, "magic and version");
+        assert_eq!(loaded.list[2].color(), Some(Color::Rgb(0xff, 0x80, 0)));
+
+        // replaced by label
+        regions.add(region("magic", 0..2, "red")).unwrap();
+        regions.remove("payload").unwrap();
+        let loaded = Regions::load(&path);
+        assert_eq!(loaded.list, regions.list);
+        assert_eq!(loaded.list[1], region("magic", 0..2, "red"));
+        assert_eq!(loaded.list.len(), 2);
+        let _ = fs::remove_file(project(&path));
+    }
+
+    #[test]
+    fn unknown_colors_are_refused() {
+        let path = file("colors");
+        let mut regions = Regions::load(&path);
+        assert_eq!(
+            regions.add(region("a", 0..4, "blurple")),
+            Err(String::from("unknown color 'blurple'"))
+        );
+        assert!(regions.list.is_empty());
+
+        // the other regions of the file are kept, but it is not overwritten
+        let text = "\
+[[region]]
+label = \"a\"
+offset = 0
+len = 4
+color = \"blurple\"
+
+[[region]]
+label = \"b\"
+offset = 4
+len = 4
+color = \"cyan\"
+";
+        fs::write(project(&path), text).unwrap();
+        let mut regions = Regions::load(&path);
+        assert_eq!(regions.list, [region("b", 4..8, "cyan")]);
+        let error = regions.error.clone().unwrap();
+        assert!(
+            error.ends_with("unknown color 'blurple' of region 'a'"),
+            "{error}"
+        );
+        assert!(regions.add(region("c", 8..9, "red")).is_err());
+        assert_eq!(fs::read_to_string(project(&path)).unwrap(), text);
+        let _ = fs::remove_file(project(&path));
+    }
+
+    #[test]
+    fn invalid_project_file_is_not_overwritten() {
+        let path = file("invalid");
+        fs::write(project(&path), "[[region]]\nlabel = ").unwrap();
+        let mut regions = Regions::load(&path);
+        assert!(regions.list.is_empty());
+        let error = regions.error.clone().unwrap();
+        assert!(error.starts_with("invalid project file"), "{error}");
+        let err = regions.add(region("a", 0..4, "red")).unwrap_err();
+        assert!(err.ends_with("it is not overwritten"), "{err}");
+        assert_eq!(
+            fs::read_to_string(project(&path)).unwrap(),
+            "[[region]]\nlabel = "
+        );
+        let _ = fs::remove_file(project(&path));
+    }
+}
@@ -0,0 +1,451 @@
+use crate::{
+    display::{DisplayOptions, Endian, Radix, WordFormat},
+    dump::{DumpFormat, DumpOptions},
+};
+
+/// Largest gap filled with zeros between two lines, so that a mistyped
+/// address does not allocate the whole address space
+const MAX_GAP: usize = 1 << 30;
+
+/// Rebuilds the bytes of a dump written with `--dump`, `xxd`, `hexdump -C`,
+/// `od` or made of hexadecimal bytes. The bytes are placed at their address,
+/// minus `options.offset`, gaps being filled with zeros. Errors are reported
+/// with their line number.
+pub fn revert(input: &str, options: &DumpOptions) -> Result<Vec<u8>, String> {
+    let parser = LineParser::new(options)?;
+    let mut content = vec![];
+    // bytes of the previous line, repeated by a `*` line until the next address
+    let mut previous: Option<(usize, Vec<u8>)> = None;
+    let mut squeezed = false;
+    let mut end = None;
+
+    for (i, line) in input.lines().enumerate() {
+        let err = |msg: String| format!("line {}: {msg}", i + 1);
+        if line.trim().is_empty() {
+            continue;
+        }
+        if options.format == DumpFormat::Plain {
+            let bytes = parse_hex(&line.split_whitespace().collect::<String>())
+                .ok_or_else(|| err(String::from("expected hexadecimal bytes")))?;
+            content.extend_from_slice(&bytes);
+            continue;
+        }
+        if line.trim() == "*" {
+            squeezed = true;
+            continue;
+        }
+
+        let (address, bytes) = parser.parse(line).map_err(err)?;
+        let offset = address
+            .checked_sub(options.offset)
+            .ok_or_else(|| err(format!("address {address:#x} is before the offset")))?;
+        if offset
+            .checked_add(bytes.len())
+            .is_none_or(|end| end > isize::MAX as usize)
+        {
+            return Err(err(format!("address {address:#x} is too large")));
+        }
+        if offset.saturating_sub(content.len()) > MAX_GAP {
+            return Err(err(format!(
+                "address {address:#x} leaves a gap of more than {MAX_GAP:#x} bytes"
+            )));
+        }
+        if let Some((last, repeated)) = previous.as_ref().filter(|_| squeezed) {
+            let mut pos = last + repeated.len();
+            while !repeated.is_empty() && pos < offset {
+                let len = std::cmp::min(repeated.len(), offset - pos);
+                write_at(&mut content, pos, &repeated[..len]);
+                pos += len;
+            }
+        }
+        squeezed = false;
+
+        if bytes.is_empty() {
+            // last line of `hexdump` and `od`, the address following the dump
+            end = Some(offset);
+        } else {
+            write_at(&mut content, offset, &bytes);
+            previous = Some((offset, bytes));
+            end = None;
+        }
+    }
+    // `od` pads the last word with zeros
+    if let Some(end) = end {
+        content.resize(end, 0);
+    }
+
+    Ok(content)
+}
+
+/// Writes `bytes` at `offset`, filling the gap with zeros
+fn write_at(content: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+    if content.len() < offset + bytes.len() {
+        content.resize(offset + bytes.len(), 0);
+    }
+    content[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn parse_hex(digits: &str) -> Option<Vec<u8>> {
+    if !digits.len().is_multiple_of(2) || !digits.is_ascii() {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Parses the lines `ADDRESS DATA CHARS` of a dump. The character column is
+/// told apart from the data by holding one character per byte, so dumps
+/// of multi-byte encodings cannot be reverted.
+struct LineParser {
+    format: DumpFormat,
+    display: DisplayOptions,
+    address_radix: Radix,
+}
+
+impl LineParser {
+    fn new(options: &DumpOptions) -> Result<Self, String> {
+        let display = match options.format {
+            DumpFormat::Hexplore => options.display,
+            // hexadecimal bytes, whatever their grouping
+            DumpFormat::Xxd | DumpFormat::Hexdump | DumpFormat::Plain => DisplayOptions::default(),
+            DumpFormat::Od => DisplayOptions {
+                radix: Radix::Octal,
+                group: 2,
+                ..DisplayOptions::default()
+            },
+        };
+        if display.word_format == WordFormat::Float {
+            return Err(String::from(
+                "words displayed as floats are rounded and cannot be reverted",
+            ));
+        }
+        Ok(LineParser {
+            format: options.format,
+            display,
+            address_radix: options.address_radix(),
+        })
+    }
+
+    /// Address and bytes of a line
+    fn parse(&self, line: &str) -> Result<(usize, Vec<u8>), String> {
+        let mut tokens = Tokens::new(line);
+        let Some((_, address_end)) = tokens.next() else {
+            return Err(String::from("expected an address"));
+        };
+        let mut address = &line[..address_end];
+        if self.format == DumpFormat::Xxd {
+            address = address
+                .strip_suffix(':')
+                .ok_or_else(|| format!("expected ':' after the address '{address}'"))?;
+        }
+        let address = usize::from_str_radix(address.trim(), self.address_radix.base() as u32)
+            .map_err(|_| format!("'{}' is not a valid address", address.trim()))?;
+
+        let mut bytes = vec![];
+        let mut data_end = address_end;
+        for (start, end) in tokens {
+            if self.chars_column(line, data_end, bytes.len()) {
+                return Ok((address, bytes));
+            }
+            let token = &line[start..end];
+            // the incomplete word at the end of the dump
+            if let Some(partial) = self.parse_partial(token)
+                && self.chars_column(line, end, bytes.len() + partial.len())
+            {
+                bytes.extend(partial);
+                return Ok((address, bytes));
+            }
+            let word = self
+                .parse_word(token)
+                .ok_or_else(|| format!("'{token}' is not a valid word"))?;
+            bytes.extend(word);
+            data_end = end;
+        }
+
+        match self.format {
+            DumpFormat::Hexplore | DumpFormat::Xxd | DumpFormat::Hexdump if !bytes.is_empty() => {
+                Err(String::from("the characters do not match the bytes"))
+            }
+            _ => Ok((address, bytes)),
+        }
+    }
+
+    /// Whether the line ends with the characters of `nb_bytes` bytes,
+    /// separated from the data ending at `data_end` by spaces
+    fn chars_column(&self, line: &str, data_end: usize, nb_bytes: usize) -> bool {
+        let (open, close) = match self.format {
+            DumpFormat::Hexdump => ("|", "|"),
+            DumpFormat::Hexplore | DumpFormat::Xxd => ("", ""),
+            // no character column
+            DumpFormat::Od | DumpFormat::Plain => return false,
+        };
+        if nb_bytes == 0 {
+            return false;
+        }
+        let Some(line) = line.strip_suffix(close) else {
+            return false;
+        };
+        let Some((start, _)) = line.char_indices().nth_back(nb_bytes - 1) else {
+            return false;
+        };
+        start >= data_end
+            && line[data_end..start]
+                .strip_suffix(open)
+                .is_some_and(is_spacing)
+    }
+
+    /// Bytes of a whole word, in the order of the file
+    fn parse_word(&self, token: &str) -> Option<Vec<u8>> {
+        let display = &self.display;
+        let group = display.group;
+        if self.format != DumpFormat::Hexplore && self.format != DumpFormat::Od {
+            return parse_hex(token);
+        }
+
+        let value = match (display.word_format, display.radix) {
+            // digits of each byte
+            (WordFormat::Raw, Radix::Hexadecimal | Radix::Binary) => {
+                let width = display.radix.cell_width();
+                if token.len() != group * width || !token.is_ascii() {
+                    return None;
+                }
+                let mut bytes = (0..group)
+                    .map(|i| {
+                        let digits = &token[i * width..(i + 1) * width];
+                        u8::from_str_radix(digits, display.radix.base() as u32).ok()
+                    })
+                    .collect::<Option<Vec<u8>>>()?;
+                if display.endian == Endian::Little {
+                    bytes.reverse();
+                }
+                return Some(bytes);
+            }
+            (WordFormat::Raw, radix) => u64::from_str_radix(token, radix.base() as u32).ok()?,
+            (WordFormat::Unsigned, _) => token.parse::<u64>().ok()?,
+            (WordFormat::Signed, _) => {
+                let value = token.parse::<i64>().ok()?;
+                let bits = 8 * group as u32;
+                let min = i64::MIN >> (64 - bits);
+                if value < min || value > !min {
+                    return None;
+                }
+                // two's complement on the bits of the word
+                value as u64 & (u64::MAX >> (64 - bits))
+            }
+            (WordFormat::Float, _) => return None,
+        };
+        if group < 8 && value >> (8 * group) != 0 {
+            return None;
+        }
+
+        Some(match display.endian {
+            Endian::Little => value.to_le_bytes()[..group].to_vec(),
+            Endian::Big => value.to_be_bytes()[8 - group..].to_vec(),
+        })
+    }
+
+    /// Bytes of the incomplete word at the end of a dump, displayed with the
+    /// digits of its bytes
+    fn parse_partial(&self, token: &str) -> Option<Vec<u8>> {
+        let display = &self.display;
+        if self.format != DumpFormat::Hexplore || display.group == 1 {
+            return None;
+        }
+        match (display.word_format, display.radix) {
+            (WordFormat::Raw, Radix::Hexadecimal | Radix::Binary) => {
+                let width = display.radix.cell_width();
+                if !token.len().is_multiple_of(width) || token.len() >= display.group * width {
+                    return None;
+                }
+                let mut bytes = token
+                    .as_bytes()
+                    .chunks(width)
+                    .map(|digits| {
+                        let digits = std::str::from_utf8(digits).ok()?;
+                        u8::from_str_radix(digits, display.radix.base() as u32).ok()
+                    })
+                    .collect::<Option<Vec<u8>>>()?;
+                if display.endian == Endian::Little {
+                    bytes.reverse();
+                }
+                Some(bytes)
+            }
+            _ => parse_hex(token).filter(|bytes| bytes.len() < display.group),
+        }
+    }
+}
+
+/// At least two spaces, separating the data from the characters
+fn is_spacing(s: &str) -> bool {
+    s.len() >= 2 && s.bytes().all(|b| b == b' ')
+}
+
+/// Whitespace separated tokens of a line, as byte ranges
+struct Tokens<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Self {
+        Tokens { line, pos: 0 }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.line[self.pos..];
+        let start = self.pos + rest.find(|c: char| !c.is_whitespace())?;
+        let end = self.line[start..]
+            .find(char::is_whitespace)
+            .map_or(self.line.len(), |len| start + len);
+        self.pos = end;
+        Some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dump;
+
+    const FORMATS: [DumpFormat; 5] = [
+        DumpFormat::Hexplore,
+        DumpFormat::Xxd,
+        DumpFormat::Hexdump,
+        DumpFormat::Od,
+        DumpFormat::Plain,
+    ];
+
+    /// Every byte value, repeated lines and an odd length
+    fn content() -> Vec<u8> {
+        [&(0..=255).collect::<Vec<u8>>()[..], &[0; 100], b"tail"].concat()
+    }
+
+    fn options(format: DumpFormat) -> DumpOptions {
+        DumpOptions {
+            format,
+            ..DumpOptions::default()
+        }
+    }
+
+    fn dump_to_string(content: &[u8], options: &DumpOptions) -> String {
+        let mut out = vec![];
+        dump::dump(&mut out, content, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in FORMATS {
+            let options = options(format);
+            let text = dump_to_string(&content(), &options);
+            assert_eq!(revert(&text, &options).unwrap(), content(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn round_trip_with_offset_and_length() {
+        for format in FORMATS {
+            let options = DumpOptions {
+                offset: 3,
+                length: Some(250),
+                cols: Some(8),
+                ..options(format)
+            };
+            let text = dump_to_string(&content(), &options);
+            assert_eq!(
+                revert(&text, &options).unwrap(),
+                content()[3..253],
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn gaps_are_zeros() {
+        let text = "00000000: 4142  AB\n00000010: 4344  CD\n";
+        let mut expected = vec![0; 0x12];
+        expected[..2].copy_from_slice(b"AB");
+        expected[0x10..].copy_from_slice(b"CD");
+        assert_eq!(revert(text, &options(DumpFormat::Xxd)).unwrap(), expected);
+    }
+
+    #[test]
+    fn squeezed_lines_are_repeated() {
+        let text = "\
+00000000  61 62 61 62 61 62 61 62  61 62 61 62 61 62 61 62  |abababababababab|
+*
+00000030  7a                                                |z|
+00000031
+";
+        let expected = [&b"ab".repeat(24)[..], b"z"].concat();
+        assert_eq!(
+            revert(text, &options(DumpFormat::Hexdump)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn huge_addresses_are_refused() {
+        let xxd = options(DumpFormat::Xxd);
+        assert_eq!(
+            revert("00000000: 4142  AB\nffffffffffffffff: 4142  AB\n", &xxd).unwrap_err(),
+            "line 2: address 0xffffffffffffffff is too large"
+        );
+        assert_eq!(
+            revert("100000000000: 4142  AB\n", &xxd).unwrap_err(),
+            "line 1: address 0x100000000000 leaves a gap of more than 0x40000000 bytes"
+        );
+        let od = options(DumpFormat::Od);
+        assert!(revert("0000000 061141\n7777777777777\n", &od).is_err());
+    }
+
+    #[test]
+    fn od_padding_is_cut() {
+        let text = "0000000 061141 000143\n0000003\n";
+        assert_eq!(revert(text, &options(DumpFormat::Od)).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn errors_give_the_line() {
+        let xxd = options(DumpFormat::Xxd);
+        assert_eq!(
+            revert("00000000: 4142  AB\n00000002 4344  CD\n", &xxd).unwrap_err(),
+            "line 2: expected ':' after the address '00000002'"
+        );
+        assert_eq!(
+            revert("\n00000000: 41zz  A.\n", &xxd).unwrap_err(),
+            "line 2: '41zz' is not a valid word"
+        );
+        assert_eq!(
+            revert("00000000: 4142  ABC\n", &xxd).unwrap_err(),
+            "line 1: 'ABC' is not a valid word"
+        );
+        assert_eq!(
+            revert("00000000: 4142 4344\n", &xxd).unwrap_err(),
+            "line 1: the characters do not match the bytes"
+        );
+        let od = options(DumpFormat::Od);
+        assert_eq!(
+            revert("0000000 061141\n0000009 000143\n", &od).unwrap_err(),
+            "line 2: '0000009' is not a valid address"
+        );
+        let offset = DumpOptions {
+            offset: 0x10,
+            ..xxd
+        };
+        assert_eq!(
+            revert("00000000: 4142  AB\n", &offset).unwrap_err(),
+            "line 1: address 0x0 is before the offset"
+        );
+        assert_eq!(
+            revert("4142\n41g2\n", &options(DumpFormat::Plain)).unwrap_err(),
+            "line 2: expected hexadecimal bytes"
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::ops::Range;
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+};
+use std::thread;
+
+use log::debug;
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Text},
+    widgets::{Bar, BarChart, BarGroup},
+};
+
+use crate::{
+    analysis::{self, entropy_from_histogram},
+    hash::CHUNK_SIZE,
+    worker::{WorkerMsg, WorkerSender},
+};
+
+/// Statistics shown in their popup, computed on a background thread
+#[derive(Debug, Default)]
+pub struct StatsJob {
+    stats: Option<Statistics>,
+    /// Range of the last statistics asked for
+    requested: Option<Range<usize>>,
+    /// Identifier of the last thread started, the former ones stopping early
+    job: Arc<AtomicU64>,
+}
+
+impl StatsJob {
+    /// Statistics of the range last asked for, `None` while they are computed
+    pub fn get(&self) -> Option<&Statistics> {
+        self.stats
+            .as_ref()
+            .filter(|stats| Some(&stats.range) == self.requested.as_ref())
+    }
+
+    /// Computes the statistics of `content[range]` in the background, unless
+    /// they already are
+    pub fn request(&mut self, content: Arc<[u8]>, range: Range<usize>, tx: WorkerSender) {
+        if self.requested.as_ref() == Some(&range) {
+            return;
+        }
+        debug!("computing statistics over {range:?}");
+        self.requested = Some(range.clone());
+        let job = self.job.fetch_add(1, Ordering::Relaxed) + 1;
+        let current = self.job.clone();
+        thread::spawn(move || {
+            let mut histogram = [0; 256];
+            for chunk in content[range.clone()].chunks(CHUNK_SIZE) {
+                if current.load(Ordering::Relaxed) != job {
+                    // another range was asked for
+                    return;
+                }
+                for &b in chunk {
+                    histogram[b as usize] += 1;
+                }
+            }
+            let stats = Statistics::from_histogram(histogram, range);
+            let _ = tx.send(WorkerMsg::Statistics(Box::new(stats)));
+        });
+    }
+
+    /// Keeps the statistics computed by a background thread, if they are
+    /// still the ones asked for
+    pub fn update(&mut self, stats: Statistics) {
+        if self.requested.as_ref() == Some(&stats.range) {
+            self.stats = Some(stats);
+        }
+    }
+
+    /// Discards the statistics, e.g. when the content changed
+    pub fn clear(&mut self) {
+        self.job.fetch_add(1, Ordering::Relaxed);
+        self.stats = None;
+        self.requested = None;
+    }
+}
+
+/// Statistics over a range of the file
+#[derive(Debug)]
+pub struct Statistics {
+    pub range: Range<usize>,
+    pub histogram: [usize; 256],
+    pub entropy: f64,
+    pub chi_square: f64,
+    pub mean: f64,
+    pub most_common: (u8, usize),
+    pub least_common: (u8, usize),
+    pub printable: f64,
+}
+
+impl Statistics {
+    /// Statistics of the bytes of `range`, counted in `histogram`
+    fn from_histogram(histogram: [usize; 256], range: Range<usize>) -> Self {
+        let total = histogram.iter().sum::<usize>();
+
+        let expected = total as f64 / 256.0;
+        let chi_square = if total == 0 {
+            0.0
+        } else {
+            histogram
+                .iter()
+                .map(|&count| (count as f64 - expected).powi(2) / expected)
+                .sum()
+        };
+
+        let sum: usize = histogram.iter().enumerate().map(|(b, &c)| b * c).sum();
+        let mean = if total == 0 {
+            0.0
+        } else {
+            sum as f64 / total as f64
+        };
+
+        // ties are resolved with the lowest byte value
+        let most_common = histogram
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, count)| count)
+            .map(|(b, &count)| (b as u8, count))
+            .unwrap_or_default();
+        let least_common = histogram
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, count)| count)
+            .map(|(b, &count)| (b as u8, count))
+            .unwrap_or_default();
+
+        let printable = histogram
+            .iter()
+            .enumerate()
+            .filter(|&(b, _)| analysis::is_printable(b as u8))
+            .map(|(_, &count)| count)
+            .sum::<usize>();
+        let printable = if total == 0 {
+            0.0
+        } else {
+            100.0 * printable as f64 / total as f64
+        };
+
+        Statistics {
+            range,
+            entropy: entropy_from_histogram(&histogram, total),
+            histogram,
+            chi_square,
+            mean,
+            most_common,
+            least_common,
+            printable,
+        }
+    }
+
+    pub fn to_text(&self) -> Text<'_> {
+        let (most, most_count) = self.most_common;
+        let (least, least_count) = self.least_common;
+        Text::from(vec![
+            Line::from(format!(
+                "range:      {:#x}..{:#x} ({} bytes)",
+                self.range.start,
+                self.range.end,
+                self.range.len()
+            )),
+            Line::from(format!("entropy:    {:.4} bits/byte", self.entropy)),
+            Line::from(format!("chi-square: {:.2}", self.chi_square)),
+            Line::from(format!("mean:       {:.4}", self.mean)),
+            Line::from(format!("most:       0x{most:02X} ({most_count} times)")),
+            Line::from(format!("least:      0x{least:02X} ({least_count} times)")),
+            Line::from(format!("printable:  {:.2}%", self.printable)),
+        ])
+    }
+
+    /// Number of byte values merged into a single bar so that the histogram
+    /// fits in `width` columns
+    pub fn bucket_size(width: u16) -> usize {
+        let mut bucket_size = 1;
+        while bucket_size < 256 && (256 / bucket_size) > width as usize {
+            bucket_size *= 2;
+        }
+
+        bucket_size
+    }
+
+    pub fn histogram_chart(&self, width: u16) -> BarChart<'_> {
+        let bucket_size = Self::bucket_size(width);
+        let bars = self
+            .histogram
+            .chunks(bucket_size)
+            .map(|bucket| {
+                Bar::default()
+                    .value(bucket.iter().sum::<usize>() as u64)
+                    .text_value(String::new())
+            })
+            .collect::<Vec<_>>();
+
+        BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(1)
+            .bar_gap(0)
+            .bar_style(Style::default().fg(Color::Cyan))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn stats_of(bytes: &[u8]) -> Statistics {
+        Statistics::from_histogram(analysis::byte_histogram(bytes), 0..bytes.len())
+    }
+
+    #[test]
+    fn values() {
+        let stats = stats_of(b"aab\x00");
+        assert_eq!(stats.entropy, 1.5);
+        assert_eq!(stats.mean, (2.0 * 97.0 + 98.0) / 4.0);
+        assert_eq!(stats.most_common, (b'a', 2));
+        // ties are resolved with the lowest byte value
+        assert_eq!(stats.least_common, (0x01, 0));
+        assert_eq!(stats.printable, 75.0);
+        // 253 values expected 1/64 times, seen 0 times
+        let expected: f64 = 4.0 / 256.0;
+        let chi_square = 253.0 * expected
+            + (2.0 - expected).powi(2) / expected
+            + 2.0 * (1.0 - expected).powi(2) / expected;
+        assert!((stats.chi_square - chi_square).abs() < 1e-9);
+
+        // uniform bytes
+        let all = (0..=255).collect::<Vec<u8>>();
+        let stats = stats_of(&all);
+        assert_eq!(
+            (stats.entropy, stats.chi_square, stats.mean),
+            (8.0, 0.0, 127.5)
+        );
+        assert_eq!(stats.most_common, (0, 1));
+
+        let empty = stats_of(&[]);
+        assert_eq!(
+            (empty.entropy, empty.chi_square, empty.mean),
+            (0.0, 0.0, 0.0)
+        );
+        assert_eq!(empty.printable, 0.0);
+    }
+
+    #[test]
+    fn histogram_buckets_fit_the_width() {
+        assert_eq!(Statistics::bucket_size(300), 1);
+        assert_eq!(Statistics::bucket_size(256), 1);
+        assert_eq!(Statistics::bucket_size(255), 2);
+        assert_eq!(Statistics::bucket_size(70), 4);
+        assert_eq!(Statistics::bucket_size(0), 256);
+    }
+
+    #[test]
+    fn computed_in_the_background() {
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerSender::new(0, tx);
+        let content: Arc<[u8]> = Arc::from(&b"hello world"[..]);
+        let recv = || match rx.recv_timeout(Duration::from_secs(5)).unwrap().2 {
+            WorkerMsg::Statistics(stats) => *stats,
+            msg => panic!("unexpected {msg:?}"),
+        };
+
+        let mut job = StatsJob::default();
+        job.request(content.clone(), 0..5, tx.clone());
+        let first = recv();
+        assert_eq!(first.range, 0..5);
+        assert_eq!(first.most_common, (b'l', 2));
+
+        // the statistics of a former range are not shown
+        job.request(content.clone(), 6..11, tx.clone());
+        job.update(first);
+        assert!(job.get().is_none());
+        job.update(recv());
+        assert_eq!(job.get().unwrap().range, 6..11);
+
+        // asked again for the same range, nothing is computed
+        job.request(content.clone(), 6..11, tx.clone());
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        job.clear();
+        assert!(job.get().is_none());
+    }
+}
@@ -0,0 +1,370 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+use std::thread;
+
+use log::debug;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::worker::{WorkerMsg, WorkerSender};
+
+pub const DEFAULT_MIN_LEN: usize = 4;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    #[default]
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl StringEncoding {
+    pub fn next(self) -> Self {
+        match self {
+            StringEncoding::Ascii => StringEncoding::Utf8,
+            StringEncoding::Utf8 => StringEncoding::Utf16Le,
+            StringEncoding::Utf16Le => StringEncoding::Utf16Be,
+            StringEncoding::Utf16Be => StringEncoding::Utf32Le,
+            StringEncoding::Utf32Le => StringEncoding::Utf32Be,
+            StringEncoding::Utf32Be => StringEncoding::Ascii,
+        }
+    }
+
+    /// Size of a code unit, strings are searched at each alignment on it
+    fn unit_size(self) -> usize {
+        match self {
+            StringEncoding::Ascii | StringEncoding::Utf8 => 1,
+            StringEncoding::Utf16Le | StringEncoding::Utf16Be => 2,
+            StringEncoding::Utf32Le | StringEncoding::Utf32Be => 4,
+        }
+    }
+
+    /// Decodes the character at the start of `bytes`, returning it along with
+    /// its length in bytes
+    fn decode(self, bytes: &[u8]) -> Option<(char, usize)> {
+        match self {
+            StringEncoding::Ascii => {
+                let b = *bytes.first()?;
+                b.is_ascii().then_some((b.into(), 1))
+            }
+            StringEncoding::Utf8 => decode_utf8(bytes),
+            StringEncoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            StringEncoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            StringEncoding::Utf32Le => {
+                let unit = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+                char::from_u32(unit).map(|c| (c, 4))
+            }
+            StringEncoding::Utf32Be => {
+                let unit = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?);
+                char::from_u32(unit).map(|c| (c, 4))
+            }
+        }
+    }
+}
+
+impl fmt::Display for StringEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StringEncoding::Ascii => "ASCII",
+            StringEncoding::Utf8 => "UTF-8",
+            StringEncoding::Utf16Le => "UTF-16LE",
+            StringEncoding::Utf16Be => "UTF-16BE",
+            StringEncoding::Utf32Le => "UTF-32LE",
+            StringEncoding::Utf32Be => "UTF-32BE",
+        };
+        write!(f, "{name}")
+    }
+}
+
+fn decode_utf8(bytes: &[u8]) -> Option<(char, usize)> {
+    let len = match *bytes.first()? {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return None,
+    };
+    let s = std::str::from_utf8(bytes.get(..len)?).ok()?;
+    s.chars().next().map(|c| (c, len))
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Option<(char, usize)> {
+    let first = from_bytes(bytes.get(..2)?.try_into().ok()?);
+    if let Some(c) = char::from_u32(first as u32) {
+        return Some((c, 2));
+    }
+    // surrogate pair
+    let second = from_bytes(bytes.get(2..4)?.try_into().ok()?);
+    char::decode_utf16([first, second])
+        .next()?
+        .ok()
+        .map(|c| (c, 4))
+}
+
+fn is_printable(c: char) -> bool {
+    c == ' ' || c == '\t' || !(c.is_control() || c.is_whitespace())
+}
+
+#[derive(Debug, Clone)]
+pub struct FoundString {
+    pub offset: usize,
+    pub text: String,
+}
+
+/// Extracts runs of at least `min_len` printable characters, like `strings`.
+/// Multi-byte encodings are scanned at every alignment, so that strings at
+/// odd offsets (e.g. in packed structures) are found too.
+pub fn extract(content: &[u8], encoding: StringEncoding, min_len: usize) -> Vec<FoundString> {
+    let mut found = (0..encoding.unit_size())
+        .flat_map(|alignment| extract_aligned(content, encoding, min_len, alignment))
+        .collect::<Vec<_>>();
+    found.sort_by_key(|string| string.offset);
+    found
+}
+
+/// Extracts the strings starting at offsets congruent to `alignment`, modulo
+/// the size of a code unit
+fn extract_aligned(
+    content: &[u8],
+    encoding: StringEncoding,
+    min_len: usize,
+    alignment: usize,
+) -> Vec<FoundString> {
+    let unit = encoding.unit_size();
+    let mut found = vec![];
+    let mut current = String::new();
+    let mut current_len = 0;
+    let mut start = 0;
+    let mut i = alignment;
+
+    while i < content.len() {
+        match encoding.decode(&content[i..]) {
+            Some((c, len)) if is_printable(c) => {
+                if current_len == 0 {
+                    start = i;
+                }
+                current.push(c);
+                current_len += 1;
+                i += len;
+            }
+            _ => {
+                if current_len >= min_len {
+                    found.push(FoundString {
+                        offset: start,
+                        text: std::mem::take(&mut current),
+                    });
+                }
+                current.clear();
+                current_len = 0;
+                i += unit;
+            }
+        }
+    }
+    if current_len >= min_len {
+        found.push(FoundString {
+            offset: start,
+            text: current,
+        });
+    }
+
+    found
+}
+
+#[derive(Debug, Default)]
+pub struct StringsPanel {
+    pub visible: bool,
+    pub encoding: StringEncoding,
+    pub min_len: usize,
+    pub filter: String,
+    /// `None` while the extraction is running
+    pub strings: Option<Vec<FoundString>>,
+    /// Indexes in `strings` matching the filter
+    filtered: Vec<usize>,
+    selected: usize,
+    pub message: Option<String>,
+}
+
+impl StringsPanel {
+    pub fn new() -> Self {
+        StringsPanel {
+            min_len: DEFAULT_MIN_LEN,
+            ..StringsPanel::default()
+        }
+    }
+
+    /// Starts the extraction with the current settings on a background thread
+    pub fn spawn_worker(&mut self, content: Arc<[u8]>, tx: WorkerSender) {
+        let encoding = self.encoding;
+        let min_len = self.min_len;
+        self.strings = None;
+        self.filtered.clear();
+        self.selected = 0;
+        thread::spawn(move || {
+            let strings = extract(&content, encoding, min_len);
+            debug!("{} {encoding} strings found", strings.len());
+            let _ = tx.send(WorkerMsg::Strings {
+                encoding,
+                min_len,
+                strings,
+            });
+        });
+    }
+
+    pub fn update(&mut self, encoding: StringEncoding, min_len: usize, strings: Vec<FoundString>) {
+        // drop results of an outdated extraction
+        if encoding != self.encoding || min_len != self.min_len {
+            return;
+        }
+        self.strings = Some(strings);
+        self.apply_filter();
+    }
+
+    pub fn apply_filter(&mut self) {
+        let Some(strings) = &self.strings else {
+            return;
+        };
+        let filter = self.filter.to_lowercase();
+        self.filtered = strings
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| filter.is_empty() || s.text.to_lowercase().contains(&filter))
+            .map(|(i, _)| i)
+            .collect();
+        self.selected = 0;
+    }
+
+    pub fn select_next(&mut self, n: usize) {
+        let last = self.filtered.len().saturating_sub(1);
+        self.selected = std::cmp::min(self.selected + n, last);
+    }
+
+    pub fn select_previous(&mut self, n: usize) {
+        self.selected = self.selected.saturating_sub(n);
+    }
+
+    pub fn selected_string(&self) -> Option<&FoundString> {
+        let idx = self.filtered.get(self.selected)?;
+        self.strings.as_ref()?.get(*idx)
+    }
+
+    /// Writes the filtered strings to `path`, in the format of `strings -t x`
+    pub fn export(&self, path: &str) -> io::Result<usize> {
+        let Some(strings) = &self.strings else {
+            return Ok(0);
+        };
+        let mut out = String::new();
+        for &idx in &self.filtered {
+            let s = &strings[idx];
+            out.push_str(&format!("{:7x} {}\n", s.offset, s.text));
+        }
+        fs::write(path, out)?;
+
+        Ok(self.filtered.len())
+    }
+
+    fn title(&self) -> String {
+        let count = match &self.strings {
+            Some(strings) => format!("{}/{}", self.filtered.len(), strings.len()),
+            None => String::from("scanning.."),
+        };
+        format!(
+            "Strings [{}, min {}] ({count}) filter: {}",
+            self.encoding, self.min_len, self.filter
+        )
+    }
+}
+
+pub const STRINGS_HELP: &str = "type: filter | Enter: jump | Tab: encoding | Left/Right: min length | ctrl+e: export | Esc: close";
+
+/// Renders a [`StringsPanel`], with the offsets of the strings converted to
+/// the displayed address space
+pub struct StringsView<'a> {
+    pub panel: &'a StringsPanel,
+    pub address_of: &'a dyn Fn(usize) -> usize,
+}
+
+impl Widget for StringsView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let StringsView { panel, address_of } = self;
+        Clear.render(area, buf);
+        let footer = panel.message.as_deref().unwrap_or(STRINGS_HELP);
+        let block = Block::default()
+            .title(panel.title())
+            .title_bottom(Line::from(footer).fg(Color::DarkGray))
+            .borders(Borders::ALL);
+        let height = block.inner(area).height as usize;
+
+        // only build the visible items, the list may be huge
+        let offset = (panel.selected / height.max(1)) * height;
+        let items = match &panel.strings {
+            Some(strings) => panel
+                .filtered
+                .iter()
+                .skip(offset)
+                .take(height)
+                .map(|&idx| {
+                    let s = &strings[idx];
+                    ListItem::new(format!("{:08x}  {}", address_of(s.offset), s.text))
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().reversed());
+        let mut state = ListState::default().with_selected(Some(panel.selected - offset));
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str) -> Vec<u8> {
+        s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn ascii_strings() {
+        let found = extract(b"\x00\x01hello\x00ab\x00world!", StringEncoding::Ascii, 4);
+        let found = found
+            .iter()
+            .map(|s| (s.offset, s.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(found, [(2, "hello"), (11, "world!")]);
+    }
+
+    #[test]
+    fn utf16_at_odd_offset() {
+        let mut content = vec![0xff];
+        content.extend(utf16le("packed"));
+        content.extend([0x00, 0x00, 0x00]);
+        let found = extract(&content, StringEncoding::Utf16Le, 4);
+        assert!(found.iter().any(|s| s.offset == 1 && s.text == "packed"));
+    }
+
+    #[test]
+    fn utf32_at_each_alignment() {
+        for shift in 0..4 {
+            let mut content = vec![0xff; shift];
+            content.extend("text".chars().flat_map(|c| (c as u32).to_be_bytes()));
+            let found = extract(&content, StringEncoding::Utf32Be, 4);
+            assert!(
+                found.iter().any(|s| s.offset == shift && s.text == "text"),
+                "shift {shift}: {found:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use log::debug;
+
+use crate::worker::{WorkerMsg, WorkerSender};
+
+/// Delay between two checks of the watched file
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls the file at `path` and sends its new content, along with the changed
+/// ranges, once its size or modification time changed and stayed the same for
+/// a poll, so that a file being written is read once complete. `content` is the
+/// content of the buffer: the watcher stops when it sent a new one, as the
+/// buffer then watches the file again, or when the buffer moved to another
+/// content or was closed.
+pub fn spawn_watcher(path: String, content: Arc<[u8]>, tx: WorkerSender) {
+    let path = PathBuf::from(path);
+    thread::spawn(move || {
+        // the file may have changed since `content` was read
+        let mut read = None;
+        let mut previous = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if !tx.is_current() {
+                debug!("'{}' no longer watched", path.display());
+                return;
+            }
+            let current = stamp(&path);
+            if current != previous {
+                // still being written
+                previous = current;
+                continue;
+            }
+            if current == read {
+                continue;
+            }
+            read = current;
+
+            // the file may be missing for a while when it is replaced
+            let Ok(new_content) = fs::read(&path) else {
+                continue;
+            };
+            let changes = changed_ranges(&content, &new_content);
+            if changes.is_empty() && content.len() == new_content.len() {
+                continue;
+            }
+            debug!("'{}' changed: {changes:?}", path.display());
+            let _ = tx.send(WorkerMsg::FileChanged {
+                content: new_content.into(),
+                changes,
+            });
+            return;
+        }
+    });
+}
+
+/// Size and modification time of the file, `None` if it cannot be read
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Ranges of `new` differing from `old`, including the appended bytes
+fn changed_ranges(old: &[u8], new: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for (i, (a, b)) in old.iter().zip(new).enumerate() {
+        if a == b {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == i => last.end = i + 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    if new.len() > old.len() {
+        match ranges.last_mut() {
+            Some(last) if last.end == old.len() => last.end = new.len(),
+            _ => ranges.push(old.len()..new.len()),
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver};
+
+    fn temp_file(test: &str, content: &[u8]) -> String {
+        let path =
+            std::env::temp_dir().join(format!("hexplore-watch-{}-{test}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Changes sent by the watchers during a few polls
+    fn changes(rx: &Receiver<(usize, u64, WorkerMsg)>) -> Vec<Vec<Range<usize>>> {
+        thread::sleep(4 * POLL_INTERVAL);
+        rx.try_iter()
+            .map(|(_, _, msg)| match msg {
+                WorkerMsg::FileChanged { changes, .. } => changes,
+                msg => panic!("unexpected {msg:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reload_fires_once_per_change() {
+        let path = temp_file("once", b"abcd");
+        let (tx, rx) = mpsc::channel();
+        let tx = WorkerSender::new(0, tx);
+        spawn_watcher(path.clone(), Arc::from(&b"abcd"[..]), tx.clone());
+        // same bytes
+        thread::sleep(2 * POLL_INTERVAL);
+        fs::write(&path, b"abcd").unwrap();
+        assert_eq!(changes(&rx), Vec::<Vec<Range<usize>>>::new());
+
+        fs::write(&path, b"abXdef").unwrap();
+        assert_eq!(changes(&rx), [vec![2..3, 4..6]]);
+        // the buffer reloads and watches the file again
+        let tx = tx.renew();
+        spawn_watcher(path.clone(), Arc::from(&b"abXdef"[..]), tx.clone());
+        thread::sleep(2 * POLL_INTERVAL);
+        fs::write(&path, b"ZbXdeg").unwrap();
+        assert_eq!(changes(&rx), [vec![0..1, 5..6]]);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn watcher_stops_with_its_content() {
+        let path = temp_file("stop", b"abcd");
+        let content: Arc<[u8]> = Arc::from(&b"abcd"[..]);
+        let tx = WorkerSender::new(0, mpsc::channel().0);
+        spawn_watcher(path.clone(), content.clone(), tx.clone());
+        assert_eq!(Arc::strong_count(&content), 2);
+        // the buffer moved to another content, or was closed
+        tx.renew();
+        thread::sleep(2 * POLL_INTERVAL);
+        assert_eq!(Arc::strong_count(&content), 1);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn changed_ranges_merge_and_append() {
+        assert_eq!(changed_ranges(b"abcd", b"abcd"), []);
+        assert_eq!(changed_ranges(b"abcd", b"xbyy"), [0..1, 2..4]);
+        assert_eq!(
+            changed_ranges(b"abcd", b"abcX12"),
+            std::slice::from_ref(&(3..6))
+        );
+        assert_eq!(changed_ranges(b"abcd", b"ab"), []);
+    }
+}
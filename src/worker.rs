@@ -0,0 +1,124 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SendError, Sender};
+
+use crate::{
+    hash::HashAlgorithm,
+    minimap::BlockSummary,
+    stats::Statistics,
+    strings::{FoundString, StringEncoding},
+};
+
+/// Results sent back by background threads to the application loop
+#[derive(Debug)]
+pub enum WorkerMsg {
+    /// Summaries of consecutive minimap blocks, starting at block index `first`
+    MinimapBlocks {
+        first: usize,
+        blocks: Vec<BlockSummary>,
+    },
+    /// Strings extracted with the given settings
+    Strings {
+        encoding: StringEncoding,
+        min_len: usize,
+        strings: Vec<FoundString>,
+    },
+    /// Progress of the reading of the file, in percent
+    FileLoading(usize),
+    /// Content of the file once read, or the reason it could not be
+    FileLoaded(Result<Arc<[u8]>, String>),
+    /// Statistics of the range last asked for
+    Statistics(Box<Statistics>),
+    /// Output of the `file` command, or the reason it could not be run
+    FileType(Result<String, String>),
+    /// Number of bytes hashed so far by the hash job `job`
+    HashProgress { job: u64, done: usize, total: usize },
+    /// Final values computed by the hash job `job`
+    HashDone {
+        job: u64,
+        results: Vec<(HashAlgorithm, String)>,
+    },
+    /// New content of a watched file, with the ranges which changed
+    FileChanged {
+        content: Arc<[u8]>,
+        changes: Vec<Range<usize>>,
+    },
+}
+
+/// Sends the results of background threads, tagged with the buffer they
+/// were computed for and the generation of its content. The generation is
+/// shared by all the senders of a buffer: once the content is replaced, the
+/// threads working on the former one fail to send and stop, and their results
+/// still in the channel are dropped.
+#[derive(Debug, Clone)]
+pub struct WorkerSender {
+    buffer: usize,
+    generation: u64,
+    current: Arc<AtomicU64>,
+    tx: Sender<(usize, u64, WorkerMsg)>,
+}
+
+impl Default for WorkerSender {
+    /// Sender of a placeholder buffer, whose results go nowhere
+    fn default() -> Self {
+        WorkerSender::new(0, mpsc::channel().0)
+    }
+}
+
+impl WorkerSender {
+    pub fn new(buffer: usize, tx: Sender<(usize, u64, WorkerMsg)>) -> Self {
+        WorkerSender {
+            buffer,
+            generation: 0,
+            current: Arc::default(),
+            tx,
+        }
+    }
+
+    /// Generation of the content the results are computed from
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Sender for a new content of the buffer, the senders of the former one
+    /// failing from now on
+    pub fn renew(&self) -> Self {
+        WorkerSender {
+            generation: self.current.fetch_add(1, Ordering::Relaxed) + 1,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the content the results are computed from is still the one
+    /// of the buffer
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::Relaxed) == self.generation
+    }
+
+    pub fn send(&self, msg: WorkerMsg) -> Result<(), SendError<WorkerMsg>> {
+        if !self.is_current() {
+            return Err(SendError(msg));
+        }
+        self.tx
+            .send((self.buffer, self.generation, msg))
+            .map_err(|SendError((_, _, msg))| SendError(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renewed_sender_stops_former_ones() {
+        let (tx, rx) = mpsc::channel();
+        let former = WorkerSender::new(3, tx);
+        let sender = former.renew();
+        assert!(former.send(WorkerMsg::FileLoading(50)).is_err());
+        assert!(sender.send(WorkerMsg::FileLoading(100)).is_ok());
+        let (buffer, generation, msg) = rx.try_recv().unwrap();
+        assert_eq!((buffer, generation), (3, sender.generation()));
+        assert!(matches!(msg, WorkerMsg::FileLoading(100)));
+    }
+}